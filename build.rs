@@ -30,6 +30,7 @@ fn _make_mega65_libc_bindings() {
 }
 
 /// update cbm kernal bindings
+#[cfg(feature = "bindgen")]
 fn _make_cbm_kernal_bindings() {
     bindgen::Builder::default()
         .header("cbm.h") // from llvm-mos-sdk/mos-targets/commodore/cbm.h
@@ -49,12 +50,6 @@ fn main() {} // Skip the script when the doc is building
 fn main() {
     //_make_mega65_libc_bindings();
     //_make_cbm_kernal_bindings();
-    #[cfg(feature = "c64")]
-    cc::Build::new()
-        .compiler("mos-c64-clang")
-        .file("src/irq.c")
-        .compile("irq");
-
     #[cfg(feature = "mega65")]
     cc::Build::new()
         .compiler("mos-mega65-clang")
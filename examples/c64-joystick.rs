@@ -20,21 +20,22 @@ extern crate mos_alloc;
 
 use core::panic::PanicInfo;
 use mos_hardware::c64;
-use mos_hardware::cia::GameController;
 use mos_hardware::cia::JoystickPosition;
+use mos_hardware::joystick::{Joystick, Port};
 use ufmt_stdio::*;
 
 #[no_mangle]
 extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
     println!("WIGGLE JOYSTICK IN PORT 2!");
+    let mut joystick = Joystick::new(Port::Two);
     loop {
-        let controller1: GameController = c64::cia1().port_a.read().into();
-
-        let (position, fire) = controller1.read_joystick();
-        while (position, fire) == controller1.read_joystick() {}
+        let previous = joystick.state();
+        while joystick.state() == previous {
+            joystick.update(c64::cia1());
+        }
 
-        let (position, fire) = controller1.read_joystick();
-        let message = match position {
+        let fire = joystick.is_fire_down();
+        let message = match joystick.position() {
             JoystickPosition::Up => "NORTH",
             JoystickPosition::Down => "SOUTH",
             JoystickPosition::Left => "WEST",
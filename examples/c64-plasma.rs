@@ -93,8 +93,9 @@ impl Plasma {
 #[no_mangle]
 extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
     const CHARSET: u16 = 0x2000; // Custom charset
-    const PAGE: u8 = vic2::ScreenBank::from_address(c64::DEFAULT_VIDEO_ADDR).bits()
-        | vic2::CharsetBank::from(CHARSET).bits();
+    const PAGE: vic2::MemorySetup = vic2::MemorySetup::from_bits(0)
+        .screen(vic2::ScreenBank::from_address(c64::DEFAULT_VIDEO_ADDR))
+        .charset(vic2::CharsetBank::from(CHARSET));
     let mut plasma = Plasma::new(CHARSET);
     unsafe { c64::vic2().screen_and_charset_bank.write(PAGE) };
     loop {
@@ -22,22 +22,19 @@ extern crate mos_alloc;
 use core::panic::PanicInfo;
 use mos_hardware::{c64, vic2::BLACK, vic2::LIGHT_GREEN};
 
-// This function is called at every triggering event.
-#[no_mangle]
-pub extern "C" fn called_every_frame() {
-    unsafe { c64::vic2().border_color.write(LIGHT_GREEN) };
-    loop {
-        if c64::vic2().raster_counter.read() > 120 {
-            break;
-        }
-    }
-    unsafe { c64::vic2().border_color.write(BLACK) };
-}
-
 #[no_mangle]
 extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
     const TRIGGER_LINE: u8 = 100;
-    c64::hardware_raster_irq(TRIGGER_LINE);
+    // This closure is called at every triggering event.
+    c64::hardware_raster_irq(TRIGGER_LINE, || {
+        unsafe { c64::vic2().border_color.write(LIGHT_GREEN) };
+        loop {
+            if c64::vic2().raster_counter.read() > 120 {
+                break;
+            }
+        }
+        unsafe { c64::vic2().border_color.write(BLACK) };
+    });
     loop {}
 }
 
@@ -19,32 +19,33 @@
 extern crate mos_alloc;
 
 use core::panic::PanicInfo;
-use mos_hardware::{c64, poke, vic2};
+use mos_hardware::{c64, poke, sprite, vic2};
 use vic2::*;
 
-/// Sprite pattern
-///
-/// Images can be converted to 24x21 sprite data using ImageMagick:
-///
-/// ```bash
-/// convert image.png -alpha off -resize 24x21! -monochrome sprite.png
-/// ```
-///
-/// and then converted into a byte array using Python:
-///
-/// ```python
-/// import numpy as np
-/// from PIL import Image
-/// image = Image.open('sprite.png')
-/// bits = (~np.asarray(image).reshape(int(24*21/8), 8))
-/// for bits_in_byte in bits.astype(int):
-///     print(int(''.join('01'[i] for i in bits_in_byte), 2), end=',')
-/// ```
-const RUST_LOGO: [u8; 63] = [
-    0, 90, 0, 1, 255, 128, 7, 239, 224, 15, 24, 240, 28, 0, 56, 63, 255, 28, 127, 255, 158, 127,
-    255, 222, 235, 195, 215, 115, 255, 142, 227, 255, 135, 99, 199, 142, 247, 195, 223, 127, 243,
-    254, 127, 241, 254, 62, 0, 60, 29, 0, 184, 15, 129, 240, 7, 255, 224, 1, 255, 128, 0, 90, 0,
-];
+/// Sprite pattern drawn as ASCII art, packed at compile time
+const RUST_LOGO: [u8; 63] = sprite!(
+    ".........#.##.#.........",
+    ".......##########.......",
+    ".....######.#######.....",
+    "....####...##...####....",
+    "...###............###...",
+    "..##############...###..",
+    ".################..####.",
+    ".#################.####.",
+    "###.#.####....####.#.###",
+    ".###..###########...###.",
+    "###...###########....###",
+    ".##...####...####...###.",
+    "####.#####....####.#####",
+    ".###########..#########.",
+    ".###########...########.",
+    "..#####...........####..",
+    "...###.#........#.###...",
+    "....#####......#####....",
+    ".....##############.....",
+    ".......##########.......",
+    ".........#.##.#.........",
+);
 
 #[no_mangle]
 extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
@@ -21,6 +21,7 @@ extern crate alloc;
 extern crate mos_alloc;
 
 use core::panic::PanicInfo;
+use mos_hardware::c64::{irq, raster};
 use mos_hardware::screen_codes;
 use mos_hardware::sid::SidTune;
 use mos_hardware::{c64, vic2};
@@ -70,15 +71,28 @@ impl SmoothScroll {
         }
     }
 
-    /// Move screen ONE pixel to the left (cycle in the interval 7..0)
+    /// First raster line of the scroll row (text starts at line 51 with default y-scroll)
+    const FIRST_LINE: u16 = 51 + 8 * SmoothScroll::YPOSITION;
+
+    /// Move scroll line ONE pixel to the left (cycle in the interval 7..0)
     #[inline]
     fn move_pixel(&mut self) {
         self.displacement = self.displacement.checked_sub(1).unwrap_or(7);
-        let mut mask = c64::vic2().control_x.read();
-        mask.set(ControlXFlags::XSCROLL, false);
-        mask = ControlXFlags::from_bits(mask.bits() + self.displacement).unwrap();
+    }
+
+    /// Apply x-scroll; called just above the scroll row
+    fn enter(&self) {
         unsafe {
-            c64::vic2().control_x.write(mask);
+            c64::vic2()
+                .control_x
+                .modify(|r| r.xscroll(self.displacement));
+        }
+    }
+
+    /// Reset x-scroll so that the rest of the screen stays in place; called below the scroll row
+    fn leave(&self) {
+        unsafe {
+            c64::vic2().control_x.modify(|r| r.xscroll(0));
         }
     }
 
@@ -172,7 +186,7 @@ impl SpriteMove {
     }
 }
 
-/// Global since the interrupt wrapper currently do not take arguments
+/// Global since they are set up before the interrupt handler is registered
 static mut SCROLL: SmoothScroll = SmoothScroll::new();
 static mut SPRITE_MOVE: SpriteMove = SpriteMove::new();
 
@@ -182,13 +196,12 @@ impl SidTune for SidFile {
 }
 static MUSIC: SidFile = SidFile;
 
-/// IRQ wrapper; called at every triggering event
+/// IRQ handler; called at every triggering event
 ///
 /// The raster time taken up by the rust code is
 /// visualized by changing the border color upon entering
 /// and exiting the function (SID tune is excluded).
-#[no_mangle]
-pub extern "C" fn called_every_frame() {
+fn called_every_frame() {
     let mut cnt: u8 = 0;
     unsafe {
         c64::vic2().border_color.write(vic2::RED);
@@ -203,6 +216,16 @@ pub extern "C" fn called_every_frame() {
     MUSIC.play(); // excluded from border color profiling
 }
 
+/// Raster split above the scroll row
+fn scroll_row_start() {
+    unsafe { SCROLL.enter() }
+}
+
+/// Raster split below the scroll row
+fn scroll_row_end() {
+    unsafe { SCROLL.leave() }
+}
+
 #[no_mangle]
 extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
     // unsafe {
@@ -217,7 +240,13 @@ extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ff
         MUSIC.to_memory();
     }
     MUSIC.init(0);
-    c64::hardware_raster_irq(20);
+    irq::install(irq::Vectors::Hardware);
+    raster::set_table(&[
+        (20, called_every_frame),
+        (SmoothScroll::FIRST_LINE - 2, scroll_row_start),
+        (SmoothScroll::FIRST_LINE + 8, scroll_row_end),
+    ]);
+    raster::start();
     loop {} // let's not return to dead BASIC
 }
 
@@ -0,0 +1,345 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Drawing on VIC-II hires and multicolor bitmaps
+//!
+//! The 8000 byte bitmap is made of 40×25 cells of 8×8 pixels, each stored as
+//! eight consecutive bytes, and the colors of each cell come from screen memory
+//! and, in multicolor mode, color RAM:
+//!
+//! | Mode       | Resolution | Pen 0          | Pen 1       | Pen 2       | Pen 3     |
+//! |------------|------------|----------------|-------------|-------------|-----------|
+//! | Hires      | 320×200    | screen low     | screen high | -           | -         |
+//! | Multicolor | 160×200    | background 0   | screen high | screen low  | color RAM |
+//!
+//! Drawing operations take a _pen_ (0-1 in hires, 0-3 in multicolor) and clip
+//! coordinates outside the bitmap.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::bitmap::{Bitmap, BitmapMode};
+//!
+//! let mut bitmap = unsafe { Bitmap::from_addresses(BitmapMode::Hires, 0x2000, 0x0400, 0xd800) };
+//! BitmapMode::Hires.apply(c64::vic2());
+//! bitmap.clear();
+//! bitmap.fill_colors(vic2::WHITE, vic2::BLACK, 0);
+//! bitmap.line(0, 0, 319, 199, 1);
+//! bitmap.circle(160, 100, 50, 1);
+//! ~~~
+
+use crate::vic2::{GraphicsMode, MOSVideoInterfaceControllerII};
+use alloc::vec::Vec;
+
+/// Size of the bitmap in bytes
+pub const BITMAP_SIZE: usize = 8000;
+/// Number of cells in screen memory and color RAM
+pub const CELLS: usize = 1000;
+/// Cells per row
+const COLUMNS: usize = 40;
+/// Height in pixels
+const HEIGHT: i16 = 200;
+
+/// Bitmap resolution and color model
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BitmapMode {
+    /// 320×200 with two colors per cell
+    Hires,
+    /// 160×200 with double wide pixels and four colors per cell
+    Multicolor,
+}
+
+impl BitmapMode {
+    /// Width in pixels
+    pub const fn width(&self) -> i16 {
+        match self {
+            Self::Hires => 320,
+            Self::Multicolor => 160,
+        }
+    }
+
+    /// Bits per pixel
+    const fn bits_per_pixel(&self) -> u8 {
+        match self {
+            Self::Hires => 1,
+            Self::Multicolor => 2,
+        }
+    }
+
+    /// Pixels per byte
+    const fn pixels_per_byte(&self) -> i16 {
+        8 / self.bits_per_pixel() as i16
+    }
+
+    /// Highest pen
+    const fn max_pen(&self) -> u8 {
+        (1 << self.bits_per_pixel()) - 1
+    }
+
+    /// Switch the VIC-II to this bitmap mode
+    pub fn apply(&self, vic: &MOSVideoInterfaceControllerII) {
+        vic.set_graphics_mode(match self {
+            Self::Hires => GraphicsMode::HiresBitmap,
+            Self::Multicolor => GraphicsMode::MulticolorBitmap,
+        });
+    }
+}
+
+/// Bitmap with its screen memory and color RAM
+pub struct Bitmap<'a> {
+    mode: BitmapMode,
+    pixels: &'a mut [u8; BITMAP_SIZE],
+    screen: &'a mut [u8; CELLS],
+    colors: &'a mut [u8; CELLS],
+}
+
+impl<'a> Bitmap<'a> {
+    /// Bitmap drawing into the given memory
+    pub fn new(
+        mode: BitmapMode,
+        pixels: &'a mut [u8; BITMAP_SIZE],
+        screen: &'a mut [u8; CELLS],
+        colors: &'a mut [u8; CELLS],
+    ) -> Self {
+        Self {
+            mode,
+            pixels,
+            screen,
+            colors,
+        }
+    }
+
+    /// Bitmap at absolute addresses, _e.g._ from `c64::layout::VicLayout`
+    ///
+    /// # Safety
+    ///
+    /// The memory must be valid and not otherwise borrowed for the lifetime of the bitmap.
+    pub unsafe fn from_addresses(mode: BitmapMode, pixels: u16, screen: u16, colors: u16) -> Self {
+        Self::new(
+            mode,
+            &mut *(pixels as *mut [u8; BITMAP_SIZE]),
+            &mut *(screen as *mut [u8; CELLS]),
+            &mut *(colors as *mut [u8; CELLS]),
+        )
+    }
+
+    pub const fn mode(&self) -> BitmapMode {
+        self.mode
+    }
+
+    /// Width in pixels (320 or 160)
+    pub const fn width(&self) -> i16 {
+        self.mode.width()
+    }
+
+    /// Height in pixels (200)
+    pub const fn height(&self) -> i16 {
+        HEIGHT
+    }
+
+    /// Clear all pixels to pen 0
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    /// Byte index and bit shift of a pixel, or `None` if outside the bitmap
+    fn locate(&self, x: i16, y: i16) -> Option<(usize, u8)> {
+        if x < 0 || y < 0 || x >= self.width() || y >= HEIGHT {
+            return None;
+        }
+        let per_byte = self.mode.pixels_per_byte();
+        let cell = (y as usize / 8) * COLUMNS + (x / per_byte) as usize;
+        let shift = (per_byte - 1 - x % per_byte) as u8 * self.mode.bits_per_pixel();
+        Some((cell * 8 + (y as usize % 8), shift))
+    }
+
+    /// Set pixel to `pen`
+    pub fn plot(&mut self, x: i16, y: i16, pen: u8) {
+        if let Some((index, shift)) = self.locate(x, y) {
+            let mask = self.mode.max_pen() << shift;
+            let byte = &mut self.pixels[index];
+            *byte = (*byte & !mask) | ((pen << shift) & mask);
+        }
+    }
+
+    /// Pen of a pixel; 0 outside the bitmap
+    pub fn get_pixel(&self, x: i16, y: i16) -> u8 {
+        match self.locate(x, y) {
+            Some((index, shift)) => (self.pixels[index] >> shift) & self.mode.max_pen(),
+            None => 0,
+        }
+    }
+
+    /// Draw a line between two points, including both ends
+    pub fn line(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, pen: u8) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+        loop {
+            self.plot(x, y, pen);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let double = 2 * error;
+            if double >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if double <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Horizontal line from `x0` to `x1`, including both ends
+    fn hline(&mut self, x0: i16, x1: i16, y: i16, pen: u8) {
+        (x0.min(x1)..=x0.max(x1)).for_each(|x| self.plot(x, y, pen));
+    }
+
+    /// Draw the outline of a rectangle
+    pub fn rect(&mut self, x: i16, y: i16, width: i16, height: i16, pen: u8) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.hline(x, right, y, pen);
+        self.hline(x, right, bottom, pen);
+        self.line(x, y, x, bottom, pen);
+        self.line(right, y, right, bottom, pen);
+    }
+
+    /// Draw a filled rectangle
+    pub fn fill_rect(&mut self, x: i16, y: i16, width: i16, height: i16, pen: u8) {
+        (y..y + height).for_each(|row| {
+            if width > 0 {
+                self.hline(x, x + width - 1, row, pen)
+            }
+        });
+    }
+
+    /// Draw a circle outline with the midpoint algorithm
+    pub fn circle(&mut self, center_x: i16, center_y: i16, radius: i16, pen: u8) {
+        let (mut x, mut y) = (radius, 0);
+        let mut error = 1 - radius;
+        while x >= y {
+            for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y)] {
+                self.plot(center_x + dx, center_y + dy, pen);
+                self.plot(center_x - dx, center_y - dy, pen);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Fill the area of same-pen pixels connected to a point with `pen`
+    pub fn flood_fill(&mut self, x: i16, y: i16, pen: u8) {
+        if self.locate(x, y).is_none() {
+            return;
+        }
+        let target = self.get_pixel(x, y);
+        let pen = pen & self.mode.max_pen();
+        if target == pen {
+            return;
+        }
+        let mut seeds = Vec::new();
+        seeds.push((x, y));
+        while let Some((x, y)) = seeds.pop() {
+            if self.get_pixel(x, y) != target {
+                continue;
+            }
+            let mut left = x;
+            while left > 0 && self.get_pixel(left - 1, y) == target {
+                left -= 1;
+            }
+            let mut right = x;
+            while right + 1 < self.width() && self.get_pixel(right + 1, y) == target {
+                right += 1;
+            }
+            self.hline(left, right, y, pen);
+            for row in [y - 1, y + 1] {
+                if !(0..HEIGHT).contains(&row) {
+                    continue;
+                }
+                // one seed per run of target pixels
+                let mut in_run = false;
+                for column in left..=right {
+                    let matches = self.get_pixel(column, row) == target;
+                    if matches && !in_run {
+                        seeds.push((column, row));
+                    }
+                    in_run = matches;
+                }
+            }
+        }
+    }
+
+    /// Copy an image of `width`×`height` pixels to a position
+    ///
+    /// `image` has rows of packed pixels in the bit depth of the mode, left to
+    /// right from the most significant bits, each row padded to whole bytes.
+    /// Byte aligned images are copied byte by byte.
+    pub fn blit(&mut self, x: i16, y: i16, width: i16, height: i16, image: &[u8]) {
+        let per_byte = self.mode.pixels_per_byte();
+        let row_bytes = ((width + per_byte - 1) / per_byte) as usize;
+        assert!(image.len() >= row_bytes * height.max(0) as usize);
+        let aligned = x % per_byte == 0 && width % per_byte == 0;
+        for row in 0..height {
+            let source = &image[row as usize * row_bytes..][..row_bytes];
+            if aligned {
+                for (i, byte) in source.iter().enumerate() {
+                    if let Some((index, _)) = self.locate(x + i as i16 * per_byte, y + row) {
+                        self.pixels[index] = *byte;
+                    }
+                }
+                continue;
+            }
+            for column in 0..width {
+                let byte = source[(column / per_byte) as usize];
+                let shift = (per_byte - 1 - column % per_byte) as u8 * self.mode.bits_per_pixel();
+                self.plot(x + column, y + row, (byte >> shift) & self.mode.max_pen());
+            }
+        }
+    }
+
+    /// Set the colors of the cell at `column` (0-39) and `row` (0-24)
+    ///
+    /// In hires mode, `color1` and `color2` are the colors of pens 1 and 0, and
+    /// `color3` is unused. In multicolor mode, they are the colors of pens 1-3.
+    pub fn set_cell_colors(&mut self, column: u8, row: u8, color1: u8, color2: u8, color3: u8) {
+        let cell = row as usize * COLUMNS + column as usize;
+        self.screen[cell] = (color1 << 4) | (color2 & 0x0f);
+        self.colors[cell] = color3;
+    }
+
+    /// Set the colors of all cells, see `set_cell_colors()`
+    pub fn fill_colors(&mut self, color1: u8, color2: u8, color3: u8) {
+        self.screen.fill((color1 << 4) | (color2 & 0x0f));
+        self.colors.fill(color3);
+    }
+
+    /// Colors of pens 1-3 of the cell at `column` and `row`, see `set_cell_colors()`
+    pub fn get_cell_colors(&self, column: u8, row: u8) -> (u8, u8, u8) {
+        let cell = row as usize * COLUMNS + column as usize;
+        let screen = self.screen[cell];
+        (screen >> 4, screen & 0x0f, self.colors[cell] & 0x0f)
+    }
+}
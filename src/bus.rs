@@ -0,0 +1,445 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Register cells and pluggable memory bus
+//!
+//! All register blocks in this crate, _e.g._ `vic2::MOSVideoInterfaceControllerII`,
+//! are built from the `RO`, `RW`, and `WO` cells defined here.
+//! On the 6502 target these are plain volatile memory accesses. On any other
+//! architecture, cells that live inside an active `HostBus` memory image are
+//! routed through the host bus which records a trace of all register
+//! accesses and may call user defined read and write hooks.
+//! This means that chip support code can be exercised off-target, _e.g._ with
+//! `cargo test` on Linux.
+//!
+//! # Examples
+//!
+//! Run a helper function on the host and inspect the register trace:
+//! ~~~
+//! use mos_hardware::bus::{Access, Bus, HostBus};
+//! use mos_hardware::c64::Cpu6510;
+//! use mos_hardware::cia::{CIA2PortA, MOSComplexInterfaceAdapter6526_2};
+//!
+//! let bus = HostBus::new();
+//! let cia2 = unsafe { bus.register::<MOSComplexInterfaceAdapter6526_2>(0xdd00) };
+//! cia2.set_vic_bank(CIA2PortA::VIC_BANK_1);
+//! assert_eq!(bus.peek(0xdd00) & 0b11, CIA2PortA::VIC_BANK_1.bits());
+//! assert!(bus.trace().contains(&Access::Write(0xdd02, 0b0000_0011)));
+//!
+//! bus.clear_trace();
+//! unsafe { bus.register::<Cpu6510>(0x0000) }.reset();
+//! assert_eq!(bus.writes(), [(0x0001, 0xf7), (0x0000, 0x2f)]);
+//! ~~~
+//!
+//! Emulate hardware behaviour with a read hook, _e.g._ a VIC-II raster counter that
+//! advances on every read:
+//! ~~~
+//! let mut bus = HostBus::new();
+//! let mut line = 0u8;
+//! bus.on_read(move |address, value| match address {
+//!     0xd012 => { line = line.wrapping_add(1); line }
+//!     _ => value,
+//! });
+//! ~~~
+
+use core::cell::UnsafeCell;
+use core::ptr::addr_of;
+
+/// Read-only register
+///
+/// The cell has an alignment of one byte so that it can be placed anywhere in
+/// a `#[repr(C, packed)]` register block.
+#[repr(C, packed)]
+pub struct RO<T: Copy> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> RO<T> {
+    /// Reads the value of the register
+    #[inline(always)]
+    pub fn read(&self) -> T {
+        unsafe { backend::read(UnsafeCell::raw_get(addr_of!(self.register))) }
+    }
+}
+
+/// Read-write register
+///
+/// The cell has an alignment of one byte so that it can be placed anywhere in
+/// a `#[repr(C, packed)]` register block.
+#[repr(C, packed)]
+pub struct RW<T: Copy> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> RW<T> {
+    /// Performs a read-modify-write operation
+    ///
+    /// # Safety
+    /// Unsafe because writes to a register are side effectful
+    #[inline(always)]
+    pub unsafe fn modify<F>(&self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        self.write(f(self.read()));
+    }
+
+    /// Reads the value of the register
+    #[inline(always)]
+    pub fn read(&self) -> T {
+        unsafe { backend::read(UnsafeCell::raw_get(addr_of!(self.register))) }
+    }
+
+    /// Writes a `value` into the register
+    ///
+    /// # Safety
+    /// Unsafe because writes to a register are side effectful
+    #[inline(always)]
+    pub unsafe fn write(&self, value: T) {
+        backend::write(UnsafeCell::raw_get(addr_of!(self.register)), value)
+    }
+}
+
+/// Write-only register
+///
+/// The cell has an alignment of one byte so that it can be placed anywhere in
+/// a `#[repr(C, packed)]` register block.
+#[repr(C, packed)]
+pub struct WO<T: Copy> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> WO<T> {
+    /// Writes `value` into the register
+    ///
+    /// # Safety
+    /// Unsafe because writes to a register are side effectful
+    #[inline(always)]
+    pub unsafe fn write(&self, value: T) {
+        backend::write(UnsafeCell::raw_get(addr_of!(self.register)), value)
+    }
+}
+
+/// Byte-wide access to a 16-bit address space
+///
+/// Support functions that touch memory outside register blocks can be written
+/// against this trait so that they run both on real hardware (`Hardware`) and
+/// on the host (`HostBus`).
+pub trait Bus {
+    /// Read byte at `address`
+    fn read(&self, address: u16) -> u8;
+
+    /// Write `value` to `address`
+    ///
+    /// # Safety
+    /// Unsafe as it writes directly to memory
+    unsafe fn write(&self, address: u16, value: u8);
+
+    /// Read-modify-write of the byte at `address`
+    ///
+    /// # Safety
+    /// Unsafe as it writes directly to memory
+    unsafe fn modify<F: FnOnce(u8) -> u8>(&self, address: u16, f: F) {
+        self.write(address, f(self.read(address)));
+    }
+
+    /// Reference to a register block, _e.g._ `sid::MOSSoundInterfaceDevice`, placed at `address`
+    ///
+    /// # Safety
+    /// `T` must be a register block built from `RO`/`RW`/`WO` cells, or another type
+    /// that is valid for any bit pattern, and on real hardware `address` must be
+    /// where that chip or memory is mapped.
+    unsafe fn register<T>(&self, address: u16) -> &T;
+}
+
+/// The memory bus of the machine the code runs on
+///
+/// # Examples
+/// ~~~
+/// use mos_hardware::bus::{Bus, Hardware};
+/// unsafe { Hardware.write(0xd020, vic2::LIGHT_GREEN) };
+/// let sid: &sid::MOSSoundInterfaceDevice = unsafe { Hardware.register(0xd400) };
+/// ~~~
+#[derive(Clone, Copy, Default)]
+pub struct Hardware;
+
+impl Bus for Hardware {
+    #[inline(always)]
+    fn read(&self, address: u16) -> u8 {
+        unsafe { backend::read(address as usize as *mut u8) }
+    }
+
+    #[inline(always)]
+    unsafe fn write(&self, address: u16, value: u8) {
+        backend::write(address as usize as *mut u8, value)
+    }
+
+    #[inline(always)]
+    unsafe fn register<T>(&self, address: u16) -> &T {
+        &*(address as usize as *const T)
+    }
+}
+
+#[cfg(target_arch = "mos")]
+mod backend {
+    #[inline(always)]
+    pub unsafe fn read<T: Copy>(ptr: *mut T) -> T {
+        ptr.read_volatile()
+    }
+
+    #[inline(always)]
+    pub unsafe fn write<T: Copy>(ptr: *mut T, value: T) {
+        ptr.write_volatile(value)
+    }
+}
+
+#[cfg(not(target_arch = "mos"))]
+pub use host::{Access, HostBus};
+
+#[cfg(not(target_arch = "mos"))]
+mod backend {
+    use super::host;
+    use core::mem::size_of;
+
+    pub unsafe fn read<T: Copy>(ptr: *mut T) -> T {
+        host::before_read(ptr as *mut u8, size_of::<T>());
+        ptr.read_unaligned()
+    }
+
+    pub unsafe fn write<T: Copy>(ptr: *mut T, value: T) {
+        ptr.write_unaligned(value);
+        host::after_write(ptr as *mut u8, size_of::<T>());
+    }
+}
+
+#[cfg(not(target_arch = "mos"))]
+mod host {
+    use super::Bus;
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::cell::{Cell, RefCell, UnsafeCell};
+    use core::ptr::null;
+
+    /// Size of the 16-bit address space
+    const MEMORY_SIZE: usize = 0x10000;
+
+    /// Read hook; receives address and stored value and returns the value seen by the reader
+    type ReadHook = Box<dyn FnMut(u16, u8) -> u8>;
+
+    /// Write hook; receives address and written value
+    type WriteHook = Box<dyn FnMut(u16, u8)>;
+
+    /// Single byte access recorded by `HostBus`
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum Access {
+        /// Read of (address, value)
+        Read(u16, u8),
+        /// Write of (address, value)
+        Write(u16, u8),
+    }
+
+    struct State {
+        memory: UnsafeCell<[u8; MEMORY_SIZE]>,
+        trace: RefCell<Vec<Access>>,
+        read_hook: RefCell<Option<ReadHook>>,
+        write_hook: RefCell<Option<WriteHook>>,
+    }
+
+    impl State {
+        /// Offset into the memory image if `ptr` points inside it
+        fn offset(&self, ptr: *mut u8) -> Option<usize> {
+            let start = self.memory.get() as usize;
+            (ptr as usize)
+                .checked_sub(start)
+                .filter(|offset| *offset < MEMORY_SIZE)
+        }
+    }
+
+    /// The active host bus of the current thread; there can be only one at a time
+    #[thread_local]
+    static ACTIVE: Cell<*const State> = Cell::new(null());
+
+    fn with_active<F: FnOnce(&State)>(f: F) {
+        let state = ACTIVE.get();
+        if !state.is_null() {
+            f(unsafe { &*state });
+        }
+    }
+
+    /// Call the hook in `slot` with it taken out, so that it can access registers itself
+    ///
+    /// The hook is put back unless it was replaced while running.
+    fn call_hook<H, R>(slot: &RefCell<Option<H>>, call: impl FnOnce(&mut H) -> R) -> Option<R> {
+        let mut hook = slot.borrow_mut().take()?;
+        let result = call(&mut hook);
+        slot.borrow_mut().get_or_insert(hook);
+        Some(result)
+    }
+
+    pub fn before_read(ptr: *mut u8, len: usize) {
+        with_active(|state| {
+            if let Some(offset) = state.offset(ptr) {
+                for i in 0..len {
+                    let address = ((offset + i) % MEMORY_SIZE) as u16;
+                    let cell = unsafe { (state.memory.get() as *mut u8).add(address as usize) };
+                    let stored = unsafe { cell.read() };
+                    let value =
+                        call_hook(&state.read_hook, |hook| hook(address, stored)).unwrap_or(stored);
+                    unsafe { cell.write(value) };
+                    state.trace.borrow_mut().push(Access::Read(address, value));
+                }
+            }
+        });
+    }
+
+    pub fn after_write(ptr: *mut u8, len: usize) {
+        with_active(|state| {
+            if let Some(offset) = state.offset(ptr) {
+                for i in 0..len {
+                    let address = ((offset + i) % MEMORY_SIZE) as u16;
+                    let value = unsafe { (*state.memory.get())[address as usize] };
+                    state.trace.borrow_mut().push(Access::Write(address, value));
+                    call_hook(&state.write_hook, |hook| hook(address, value));
+                }
+            }
+        });
+    }
+
+    /// In-memory 64 KiB bus for running chip support code on the host
+    ///
+    /// Register blocks are mapped into the memory image with `register()` and all
+    /// accesses through their `RO`/`RW`/`WO` cells are recorded in a trace.
+    /// Each thread can have one active host bus at a time, so tests running in
+    /// parallel each get their own. Hooks may themselves access registers; those
+    /// accesses are traced but do not call the hooks again.
+    pub struct HostBus {
+        state: Box<State>,
+    }
+
+    impl HostBus {
+        /// Create zero-initialized memory image and make it the active bus
+        ///
+        /// Panics if the current thread already has an active host bus.
+        pub fn new() -> Self {
+            let state = Box::new(State {
+                memory: UnsafeCell::new([0; MEMORY_SIZE]),
+                trace: RefCell::new(Vec::new()),
+                read_hook: RefCell::new(None),
+                write_hook: RefCell::new(None),
+            });
+            assert!(
+                ACTIVE.get().is_null(),
+                "a HostBus is already active on this thread"
+            );
+            ACTIVE.set(&*state);
+            Self { state }
+        }
+
+        /// Mutable reference to a register block placed at `address`
+        ///
+        /// Needed for support functions that take `&mut self`, _e.g._
+        /// `cia::TimeOfDay::set_time_bcd_bool()`.
+        ///
+        /// # Safety
+        /// As for `Bus::register()`; in addition, no other reference to the
+        /// same memory may be in use while the returned one is.
+        pub unsafe fn register_mut<T>(&mut self, address: u16) -> &mut T {
+            assert!(address as usize + core::mem::size_of::<T>() <= MEMORY_SIZE);
+            &mut *((self.state.memory.get() as *mut u8).add(address as usize) as *mut T)
+        }
+
+        /// Read byte from memory image without tracing or hooks
+        pub fn peek(&self, address: u16) -> u8 {
+            unsafe { (*self.state.memory.get())[address as usize] }
+        }
+
+        /// Write byte to memory image without tracing or hooks
+        pub fn poke(&self, address: u16, value: u8) {
+            unsafe { (*self.state.memory.get())[address as usize] = value }
+        }
+
+        /// Copy `bytes` into the memory image starting at `address`
+        pub fn load(&self, address: u16, bytes: &[u8]) {
+            bytes
+                .iter()
+                .enumerate()
+                .for_each(|(i, byte)| self.poke(address.wrapping_add(i as u16), *byte));
+        }
+
+        /// Call `hook` before every traced read; the returned value is what the reader sees
+        pub fn on_read<F: FnMut(u16, u8) -> u8 + 'static>(&mut self, hook: F) {
+            *self.state.read_hook.borrow_mut() = Some(Box::new(hook));
+        }
+
+        /// Call `hook` after every traced write
+        pub fn on_write<F: FnMut(u16, u8) + 'static>(&mut self, hook: F) {
+            *self.state.write_hook.borrow_mut() = Some(Box::new(hook));
+        }
+
+        /// All recorded accesses in chronological order
+        pub fn trace(&self) -> Vec<Access> {
+            self.state.trace.borrow().clone()
+        }
+
+        /// Recorded writes as (address, value) pairs
+        pub fn writes(&self) -> Vec<(u16, u8)> {
+            self.state
+                .trace
+                .borrow()
+                .iter()
+                .filter_map(|access| match *access {
+                    Access::Write(address, value) => Some((address, value)),
+                    Access::Read(..) => None,
+                })
+                .collect()
+        }
+
+        /// Forget all recorded accesses
+        pub fn clear_trace(&self) {
+            self.state.trace.borrow_mut().clear();
+        }
+    }
+
+    impl Default for HostBus {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Drop for HostBus {
+        fn drop(&mut self) {
+            ACTIVE.set(null());
+        }
+    }
+
+    impl Bus for HostBus {
+        fn read(&self, address: u16) -> u8 {
+            unsafe {
+                super::backend::read((self.state.memory.get() as *mut u8).add(address as usize))
+            }
+        }
+
+        unsafe fn write(&self, address: u16, value: u8) {
+            super::backend::write(
+                (self.state.memory.get() as *mut u8).add(address as usize),
+                value,
+            )
+        }
+
+        unsafe fn register<T>(&self, address: u16) -> &T {
+            assert!(address as usize + core::mem::size_of::<T>() <= MEMORY_SIZE);
+            &*((self.state.memory.get() as *const u8).add(address as usize) as *const T)
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Scoped memory banking with the 6510 CPU port
+//!
+//! `Banking` switches the memory configuration and restores the previous one
+//! when dropped. Interrupts are disabled while I/O is banked out, as interrupt
+//! handlers expect to reach the VIC-II and CIAs. Note that NMIs cannot be disabled.
+//! Only the banking bits (0-2) are changed so that datasette bits are left alone.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::{banking, CpuPortFlags};
+//!
+//! // read the first glyph of the character ROM
+//! let glyph: [u8; 8] = banking::with_banking(CpuPortFlags::RAM_CHAR_RAM, || unsafe {
+//!     *(0xd000 as *const [u8; 8])
+//! });
+//!
+//! // or copy the mixed case charset
+//! let mut charset = [0u8; 2048];
+//! banking::copy_char_rom(banking::MIXED_CASE_OFFSET, &mut charset);
+//! ~~~
+
+use super::cpu6510::{CpuPortFlags, CPU_PORT};
+use crate::interrupt;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Address of the character ROM when banked in (0xd000)
+pub const CHAR_ROM_ADDRESS: u16 = 0xd000;
+
+/// Size of the character ROM in bytes
+pub const CHAR_ROM_SIZE: usize = 0x1000;
+
+/// Offset of the upper case and graphics charset in the character ROM
+pub const UPPER_CASE_OFFSET: u16 = 0x0000;
+
+/// Offset of the mixed case charset in the character ROM
+pub const MIXED_CASE_OFFSET: u16 = 0x0800;
+
+/// Bits 0-2 of the CPU port controlling memory banking
+const BANKING_MASK: CpuPortFlags = CpuPortFlags::LORAM
+    .union(CpuPortFlags::HIRAM)
+    .union(CpuPortFlags::CHAREN);
+
+/// True if `config` has I/O visible at 0xd000-0xdfff
+pub const fn is_io_visible(config: CpuPortFlags) -> bool {
+    config.contains(CpuPortFlags::CHAREN)
+        && config.intersects(CpuPortFlags::LORAM.union(CpuPortFlags::HIRAM))
+}
+
+/// Guard that restores the previous memory configuration when dropped
+///
+/// # Examples
+/// ~~~
+/// {
+///     let _banking = Banking::new(CpuPortFlags::RAM_IO_RAM);
+///     // RAM at 0xa000-0xbfff and 0xe000-0xffff
+/// }
+/// // memory configuration restored
+/// ~~~
+#[must_use = "the previous memory configuration is restored when the guard is dropped"]
+pub struct Banking {
+    /// Configuration before the guard was created
+    previous: CpuPortFlags,
+    /// Interrupt state to restore when dropped
+    interrupts_enabled: bool,
+}
+
+impl Banking {
+    /// Switch to the banking bits of `config`
+    pub fn new(config: CpuPortFlags) -> Self {
+        let was_enabled = interrupt::disable();
+        let previous = unsafe { (*CPU_PORT).read() };
+        let next = previous.difference(BANKING_MASK) | config.intersection(BANKING_MASK);
+        unsafe { (*CPU_PORT).write(next) };
+        compiler_fence(Ordering::SeqCst);
+        let interrupts_enabled = match is_io_visible(next) {
+            true => {
+                unsafe { interrupt::restore(was_enabled) };
+                false
+            }
+            false => was_enabled,
+        };
+        Self {
+            previous,
+            interrupts_enabled,
+        }
+    }
+
+    /// Memory configuration that is restored when dropped
+    pub const fn previous(&self) -> CpuPortFlags {
+        self.previous
+    }
+}
+
+impl Drop for Banking {
+    fn drop(&mut self) {
+        compiler_fence(Ordering::SeqCst);
+        let restore = |current: CpuPortFlags| {
+            current.difference(BANKING_MASK) | self.previous.intersection(BANKING_MASK)
+        };
+        interrupt::free(|| unsafe { (*CPU_PORT).modify(restore) });
+        unsafe { interrupt::restore(self.interrupts_enabled) };
+    }
+}
+
+/// Call `f` with memory configuration `config` and restore the previous configuration afterwards
+pub fn with_banking<F: FnOnce() -> R, R>(config: CpuPortFlags, f: F) -> R {
+    let _banking = Banking::new(config);
+    f()
+}
+
+/// Copy from the character ROM starting at `offset` (0-0xfff) into `destination`
+///
+/// See `UPPER_CASE_OFFSET` and `MIXED_CASE_OFFSET` for the two charsets.
+pub fn copy_char_rom(offset: u16, destination: &mut [u8]) {
+    assert!(offset as usize + destination.len() <= CHAR_ROM_SIZE);
+    let source = (CHAR_ROM_ADDRESS + offset) as *const u8;
+    with_banking(CpuPortFlags::RAM_CHAR_RAM, || {
+        for (i, byte) in destination.iter_mut().enumerate() {
+            *byte = unsafe { source.add(i).read_volatile() };
+        }
+    });
+}
+
+/// Copy RAM starting at `address` into `destination`, including RAM under the ROMs and I/O
+///
+/// `destination` must itself be in RAM.
+pub fn copy_from_ram(address: u16, destination: &mut [u8]) {
+    assert!(address as usize + destination.len() <= 0x10000);
+    let source = address as *const u8;
+    with_banking(CpuPortFlags::RAM_RAM_RAM, || {
+        for (i, byte) in destination.iter_mut().enumerate() {
+            *byte = unsafe { source.add(i).read_volatile() };
+        }
+    });
+}
+
+/// Copy `source` to RAM starting at `address`, including RAM under the ROMs and I/O
+///
+/// Writes to ROM areas always end up in RAM, but this also works for 0xd000-0xdfff.
+/// `source` must itself be in RAM.
+pub fn copy_to_ram(address: u16, source: &[u8]) {
+    assert!(address as usize + source.len() <= 0x10000);
+    let destination = address as *mut u8;
+    with_banking(CpuPortFlags::RAM_RAM_RAM, || {
+        for (i, byte) in source.iter().enumerate() {
+            unsafe { destination.add(i).write_volatile(*byte) };
+        }
+    });
+}
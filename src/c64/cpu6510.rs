@@ -1,7 +1,7 @@
+use crate::bus::RW;
 use bitflags::bitflags;
 use core::mem::size_of;
 use static_assertions::const_assert;
-use volatile_register::RW;
 
 pub struct Cpu6510 {
     direction: RW<CpuPortDdrFlags>,
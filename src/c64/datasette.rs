@@ -0,0 +1,580 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Datasette (tape) control and the standard CBM tape encoding
+//!
+//! The motor, button sense and write line are bits 3-5 of the 6510 CPU port,
+//! and the read line is the FLAG pin of CIA1 which triggers on falling edges.
+//! Data is recorded as square wave pulses of three lengths:
+//!
+//! | Symbol            | Pulses          |
+//! |-------------------|-----------------|
+//! | Bit 0             | short, medium   |
+//! | Bit 1             | medium, short   |
+//! | Byte marker       | long, medium    |
+//! | End of data       | long, short     |
+//!
+//! Each byte starts with a marker, followed by eight bits LSB first and an odd
+//! parity bit. A block is a leader of short pulses, the countdown bytes
+//! `0x89..=0x81`, the data, an XOR checksum and an end of data marker, after
+//! which the block is repeated with countdown `0x09..=0x01`. A file is a 192
+//! byte header block followed by a data block.
+//!
+//! CIA1 timer B measures pulses and interrupts are disabled during transfers,
+//! so the KERNAL keyboard interrupt and its motor control are paused.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::datasette::{self, Header};
+//!
+//! let savegame = [1u8, 2, 3, 4];
+//! if datasette::is_button_pressed() {
+//!     datasette::save(b"SAVEGAME", 0xc000, &savegame)?;
+//! }
+//! let mut buffer = [0u8; 1024];
+//! let (header, length) = datasette::load(b"SAVEGAME", &mut buffer)?;
+//! ~~~
+
+use super::cia1_control;
+use super::cpu6510::{CpuPortFlags, CPU_PORT};
+use crate::cbm_kernal::StatusFlags;
+use crate::cia::{InterruptControl, Timer, TimerMode};
+use crate::interrupt;
+
+/// Length of a short pulse in cycles (about 2840 Hz on PAL)
+pub const SHORT_PULSE: u16 = 352;
+/// Length of a medium pulse in cycles (about 1953 Hz on PAL)
+pub const MEDIUM_PULSE: u16 = 512;
+/// Length of a long pulse in cycles (about 1488 Hz on PAL)
+pub const LONG_PULSE: u16 = 672;
+
+/// Short pulses in the leader of a header block (about 10 seconds)
+pub const HEADER_LEADER: u16 = 0x6a00;
+/// Short pulses in the leader of a data block (about 2 seconds)
+pub const DATA_LEADER: u16 = 0x1a00;
+/// Short pulses between the two copies of a block
+const INTERBLOCK_GAP: u16 = 79;
+/// Short pulses to detect a leader when reading
+const MIN_LEADER: u16 = 64;
+
+/// Size of a header block
+pub const HEADER_SIZE: usize = 192;
+/// Length of the file name in a header
+pub const NAME_LENGTH: usize = 16;
+
+/// Pulses shorter than this are noise
+const MIN_PULSE: u16 = SHORT_PULSE / 2;
+/// Boundary between short and medium pulses
+const SHORT_MEDIUM: u16 = (SHORT_PULSE + MEDIUM_PULSE) / 2;
+/// Boundary between medium and long pulses
+const MEDIUM_LONG: u16 = (MEDIUM_PULSE + LONG_PULSE) / 2;
+/// Cycles without pulses before a read gives up (about 4 seconds)
+const READ_TIMEOUT: u32 = 4_000_000;
+
+/// Cycles for the motor to reach full speed
+const MOTOR_SPINUP: u32 = 300_000;
+
+/// Turn the tape motor on or off
+pub fn set_motor(on: bool) {
+    unsafe {
+        (*CPU_PORT).modify(|mut v| {
+            v.set(CpuPortFlags::DATASETTE_MOTOR_OFF, !on);
+            v
+        })
+    };
+}
+
+/// True if the tape motor is on
+pub fn is_motor_on() -> bool {
+    unsafe {
+        !(*CPU_PORT)
+            .read()
+            .contains(CpuPortFlags::DATASETTE_MOTOR_OFF)
+    }
+}
+
+/// True if PLAY, RECORD, FFWD or REW is pressed
+pub fn is_button_pressed() -> bool {
+    unsafe {
+        !(*CPU_PORT)
+            .read()
+            .contains(CpuPortFlags::DATASETTE_BUTTON_OFF)
+    }
+}
+
+/// Set the level of the write line
+fn set_signal(high: bool) {
+    unsafe {
+        (*CPU_PORT).modify(|mut v| {
+            v.set(CpuPortFlags::DATASETTE_SIGNAL, high);
+            v
+        })
+    };
+}
+
+/// Wait for about `cycles` using the free running CIA1 timer B
+fn delay(cycles: u32) {
+    let timer = cia1_control();
+    let mut previous = timer.timer_value(Timer::B);
+    let mut elapsed = 0u32;
+    while elapsed < cycles {
+        let now = timer.timer_value(Timer::B);
+        elapsed += previous.wrapping_sub(now) as u32;
+        previous = now;
+    }
+}
+
+/// Type of a file or block, stored as the first byte of a header
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum FileType {
+    /// Program loaded to the current BASIC start
+    RelocatableProgram = 1,
+    /// Data block of a sequential file
+    DataBlock = 2,
+    /// Program loaded to its start address
+    Program = 3,
+    /// Header of a sequential file
+    DataHeader = 4,
+    /// End of tape marker
+    EndOfTape = 5,
+}
+
+impl TryFrom<u8> for FileType {
+    type Error = StatusFlags;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::RelocatableProgram),
+            2 => Ok(Self::DataBlock),
+            3 => Ok(Self::Program),
+            4 => Ok(Self::DataHeader),
+            5 => Ok(Self::EndOfTape),
+            _ => Err(StatusFlags::READ_ERROR),
+        }
+    }
+}
+
+/// Header block of a file
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Header {
+    pub file_type: FileType,
+    /// Load address
+    pub start: u16,
+    /// Address after the last byte
+    pub end: u16,
+    /// PETSCII file name padded with spaces
+    pub name: [u8; NAME_LENGTH],
+}
+
+impl Header {
+    /// Program header for `length` bytes at `start`; `name` is truncated to 16 characters
+    pub fn new(name: &[u8], start: u16, length: usize) -> Self {
+        let mut padded = [b' '; NAME_LENGTH];
+        let length_of_name = name.len().min(NAME_LENGTH);
+        padded[..length_of_name].copy_from_slice(&name[..length_of_name]);
+        Self {
+            file_type: FileType::Program,
+            start,
+            end: start.wrapping_add(length as u16),
+            name: padded,
+        }
+    }
+
+    /// Number of bytes in the data block
+    pub const fn length(&self) -> usize {
+        self.end.wrapping_sub(self.start) as usize
+    }
+
+    /// True if the file name starts with `pattern`; an empty pattern matches all files
+    pub fn matches(&self, pattern: &[u8]) -> bool {
+        self.name.starts_with(pattern)
+    }
+
+    /// Header block as stored on tape; bytes after the name are spaces
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [b' '; HEADER_SIZE];
+        bytes[0] = self.file_type as u8;
+        bytes[1..3].copy_from_slice(&self.start.to_le_bytes());
+        bytes[3..5].copy_from_slice(&self.end.to_le_bytes());
+        bytes[5..5 + NAME_LENGTH].copy_from_slice(&self.name);
+        bytes
+    }
+
+    /// Parse a header block; fails with `READ_ERROR` for an unknown file type
+    pub fn from_bytes(bytes: &[u8; HEADER_SIZE]) -> Result<Self, StatusFlags> {
+        let mut name = [0u8; NAME_LENGTH];
+        name.copy_from_slice(&bytes[5..5 + NAME_LENGTH]);
+        Ok(Self {
+            file_type: FileType::try_from(bytes[0])?,
+            start: u16::from_le_bytes([bytes[1], bytes[2]]),
+            end: u16::from_le_bytes([bytes[3], bytes[4]]),
+            name,
+        })
+    }
+}
+
+/// Pulse length classes
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Pulse {
+    Short,
+    Medium,
+    Long,
+}
+
+impl Pulse {
+    /// Classify a pulse of `cycles`; returns `None` for noise
+    pub const fn from_cycles(cycles: u16) -> Option<Self> {
+        match cycles {
+            c if c < MIN_PULSE => None,
+            c if c < SHORT_MEDIUM => Some(Self::Short),
+            c if c < MEDIUM_LONG => Some(Self::Medium),
+            _ => Some(Self::Long),
+        }
+    }
+
+    /// Nominal length in cycles
+    pub const fn cycles(&self) -> u16 {
+        match self {
+            Self::Short => SHORT_PULSE,
+            Self::Medium => MEDIUM_PULSE,
+            Self::Long => LONG_PULSE,
+        }
+    }
+}
+
+/// Odd parity bit of a byte as recorded after its data bits
+const fn parity(byte: u8) -> bool {
+    byte.count_ones().is_multiple_of(2)
+}
+
+/// Pulse-level tape writer; interrupts must be disabled while writing
+pub struct Writer {
+    /// True if the write line is high
+    level: bool,
+    /// Timer value when last polled
+    last: u16,
+    /// Cycles the previous wait overshot, subtracted from the next
+    overshoot: u32,
+}
+
+impl Writer {
+    /// Start a free running CIA1 timer B for pulse timing
+    pub fn new() -> Self {
+        let timer = cia1_control();
+        timer.start_timer(Timer::B, u16::MAX, TimerMode::Continuous);
+        set_signal(false);
+        Self {
+            level: false,
+            last: timer.timer_value(Timer::B),
+            overshoot: 0,
+        }
+    }
+
+    /// Wait until `cycles` after the end of the previous wait
+    fn wait(&mut self, cycles: u32) {
+        let timer = cia1_control();
+        let mut elapsed = self.overshoot;
+        while elapsed < cycles {
+            let now = timer.timer_value(Timer::B);
+            elapsed += self.last.wrapping_sub(now) as u32;
+            self.last = now;
+        }
+        self.overshoot = elapsed - cycles;
+    }
+
+    /// Write one full square wave period
+    pub fn write_pulse(&mut self, pulse: Pulse) {
+        let half = (pulse.cycles() / 2) as u32;
+        for _ in 0..2 {
+            self.level = !self.level;
+            set_signal(self.level);
+            self.wait(half);
+        }
+    }
+
+    /// Write `count` short pulses
+    pub fn write_leader(&mut self, count: u16) {
+        (0..count).for_each(|_| self.write_pulse(Pulse::Short));
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        match bit {
+            false => {
+                self.write_pulse(Pulse::Short);
+                self.write_pulse(Pulse::Medium);
+            }
+            true => {
+                self.write_pulse(Pulse::Medium);
+                self.write_pulse(Pulse::Short);
+            }
+        }
+    }
+
+    /// Write a byte marker, eight bits LSB first and the parity bit
+    pub fn write_byte(&mut self, byte: u8) {
+        self.write_pulse(Pulse::Long);
+        self.write_pulse(Pulse::Medium);
+        (0..8).for_each(|i| self.write_bit(byte & (1 << i) != 0));
+        self.write_bit(parity(byte));
+    }
+
+    /// Write the end of data marker
+    pub fn write_end(&mut self) {
+        self.write_pulse(Pulse::Long);
+        self.write_pulse(Pulse::Short);
+    }
+
+    /// Write a block and its repeated copy after a leader of `leader` short pulses
+    pub fn write_block(&mut self, data: &[u8], leader: u16) {
+        self.write_leader(leader);
+        for countdown in [0x89, 0x09] {
+            (0..9).for_each(|i| self.write_byte(countdown - i));
+            let mut checksum = 0;
+            for byte in data {
+                checksum ^= byte;
+                self.write_byte(*byte);
+            }
+            self.write_byte(checksum);
+            self.write_end();
+            self.write_leader(INTERBLOCK_GAP);
+        }
+    }
+}
+
+impl Default for Writer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pulse-level tape reader; interrupts must be disabled while reading
+pub struct Reader {
+    /// Timer value at the previous falling edge
+    previous: u16,
+}
+
+impl Reader {
+    /// Start a free running CIA1 timer B for pulse timing
+    pub fn new() -> Self {
+        let timer = cia1_control();
+        timer.start_timer(Timer::B, u16::MAX, TimerMode::Continuous);
+        // clear a pending FLAG
+        let _ = timer.interrupt.read();
+        Self {
+            previous: timer.timer_value(Timer::B),
+        }
+    }
+
+    /// Wait for the next falling edge and classify the time since the previous one
+    ///
+    /// Fails with `READ_TIME_OUT` if no pulses arrive.
+    pub fn read_pulse(&mut self) -> Result<Pulse, StatusFlags> {
+        let timer = cia1_control();
+        let mut last_poll = self.previous;
+        let mut waited = 0u32;
+        loop {
+            let flags = timer.interrupt.read();
+            let now = timer.timer_value(Timer::B);
+            waited += last_poll.wrapping_sub(now) as u32;
+            last_poll = now;
+            if flags.contains(InterruptControl::FLAG) {
+                let cycles = match waited {
+                    0..=0xffff => waited as u16,
+                    _ => u16::MAX,
+                };
+                self.previous = now;
+                if let Some(pulse) = Pulse::from_cycles(cycles) {
+                    return Ok(pulse);
+                }
+            }
+            if waited > READ_TIMEOUT {
+                return Err(StatusFlags::READ_TIME_OUT);
+            }
+        }
+    }
+
+    /// Skip pulses until a leader of short pulses has been found
+    pub fn find_leader(&mut self) -> Result<(), StatusFlags> {
+        let mut count = 0;
+        while count < MIN_LEADER {
+            count = match self.read_pulse()? {
+                Pulse::Short => count + 1,
+                _ => 0,
+            };
+        }
+        Ok(())
+    }
+
+    /// Skip short pulses and read the next byte; returns `None` at an end of data marker
+    pub fn read_byte(&mut self) -> Result<Option<u8>, StatusFlags> {
+        let mut pulse = self.read_pulse()?;
+        while pulse == Pulse::Short {
+            pulse = self.read_pulse()?;
+        }
+        if pulse != Pulse::Long {
+            return Err(StatusFlags::READ_ERROR);
+        }
+        match self.read_pulse()? {
+            Pulse::Medium => {}
+            Pulse::Short => return Ok(None),
+            Pulse::Long => return Err(StatusFlags::READ_ERROR),
+        }
+        let mut byte = 0u8;
+        for i in 0..8 {
+            if self.read_bit()? {
+                byte |= 1 << i;
+            }
+        }
+        match self.read_bit()? == parity(byte) {
+            true => Ok(Some(byte)),
+            false => Err(StatusFlags::READ_ERROR),
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, StatusFlags> {
+        match (self.read_pulse()?, self.read_pulse()?) {
+            (Pulse::Short, Pulse::Medium) => Ok(false),
+            (Pulse::Medium, Pulse::Short) => Ok(true),
+            _ => Err(StatusFlags::READ_ERROR),
+        }
+    }
+
+    /// Read one copy of a block into `data`, which must match the block length
+    ///
+    /// Returns `true` for the repeated copy. Fails with `SHORT_BLOCK` or
+    /// `LONG_BLOCK` if the length differs, and with `CHECKSUM_ERROR` if the
+    /// checksum does not match.
+    pub fn read_block(&mut self, data: &mut [u8]) -> Result<bool, StatusFlags> {
+        self.find_leader()?;
+        let first = self.read_byte()?.ok_or(StatusFlags::SHORT_BLOCK)?;
+        let repeated = match first {
+            0x89 => false,
+            0x09 => true,
+            _ => return Err(StatusFlags::READ_ERROR),
+        };
+        for expected in (1..first & 0x0f).rev() {
+            if self.read_byte()? != Some(expected | (first & 0x80)) {
+                return Err(StatusFlags::READ_ERROR);
+            }
+        }
+        let mut checksum = 0;
+        for target in data.iter_mut() {
+            *target = self.read_byte()?.ok_or(StatusFlags::SHORT_BLOCK)?;
+            checksum ^= *target;
+        }
+        let stored = self.read_byte()?.ok_or(StatusFlags::SHORT_BLOCK)?;
+        if self.read_byte()?.is_some() {
+            return Err(StatusFlags::LONG_BLOCK);
+        }
+        match stored == checksum {
+            true => Ok(repeated),
+            false => Err(StatusFlags::CHECKSUM_ERROR),
+        }
+    }
+
+    /// Read a block, falling back to the repeated copy if the first has errors
+    pub fn read_block_with_repeat(&mut self, data: &mut [u8]) -> Result<(), StatusFlags> {
+        match self.read_block(data) {
+            Ok(true) => Ok(()),
+            // skip the repeated copy
+            Ok(false) => {
+                let _ = self.find_leader().and_then(|_| self.skip_block());
+                Ok(())
+            }
+            Err(_) => self.read_block(data).map(|_| ()),
+        }
+    }
+
+    /// Read bytes until the end of data marker
+    fn skip_block(&mut self) -> Result<(), StatusFlags> {
+        while self.read_byte()?.is_some() {}
+        Ok(())
+    }
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run `f` with the motor on and interrupts disabled; fails if no button is pressed
+fn with_motor<F, R>(f: F) -> Result<R, StatusFlags>
+where
+    F: FnOnce() -> Result<R, StatusFlags>,
+{
+    if !is_button_pressed() {
+        return Err(StatusFlags::DEVICE_NOT_PRESENT);
+    }
+    interrupt::free(|| {
+        cia1_control().start_timer(Timer::B, u16::MAX, TimerMode::Continuous);
+        set_motor(true);
+        delay(MOTOR_SPINUP);
+        let result = f();
+        set_motor(false);
+        result
+    })
+}
+
+/// Save `data` as a program file with load address `start`
+///
+/// RECORD and PLAY must be pressed, otherwise `DEVICE_NOT_PRESENT` is returned.
+pub fn save(name: &[u8], start: u16, data: &[u8]) -> Result<(), StatusFlags> {
+    let header = Header::new(name, start, data.len());
+    with_motor(|| {
+        let mut writer = Writer::new();
+        writer.write_block(&header.to_bytes(), HEADER_LEADER);
+        writer.write_block(data, DATA_LEADER);
+        Ok(())
+    })
+}
+
+/// Load the first file whose name starts with `name` into `destination`
+///
+/// Returns the header and the number of bytes loaded. PLAY must be pressed,
+/// otherwise `DEVICE_NOT_PRESENT` is returned. Fails with `END_OF_IDENTITY`
+/// at an end of tape marker and with `LONG_BLOCK` if the file does not fit.
+pub fn load(name: &[u8], destination: &mut [u8]) -> Result<(Header, usize), StatusFlags> {
+    with_motor(|| {
+        let mut reader = Reader::new();
+        let header = loop {
+            let mut bytes = [0u8; HEADER_SIZE];
+            reader.read_block_with_repeat(&mut bytes)?;
+            let header = Header::from_bytes(&bytes)?;
+            match header.file_type {
+                FileType::EndOfTape => return Err(StatusFlags::END_OF_IDENTITY),
+                FileType::Program | FileType::RelocatableProgram if header.matches(name) => {
+                    break header
+                }
+                // skip both copies of the data block of another program
+                FileType::Program | FileType::RelocatableProgram => {
+                    for _ in 0..2 {
+                        reader.find_leader()?;
+                        match reader.skip_block() {
+                            Err(error) if error != StatusFlags::READ_ERROR => return Err(error),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        };
+        let length = header.length();
+        let data = destination
+            .get_mut(..length)
+            .ok_or(StatusFlags::LONG_BLOCK)?;
+        reader.read_block_with_repeat(data)?;
+        Ok((header, length))
+    })
+}
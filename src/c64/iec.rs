@@ -0,0 +1,401 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! IEC serial bus driver that does not need the KERNAL
+//!
+//! Implements the Commodore serial bus protocol on CIA2 port A with the C64 as
+//! bus controller: commands are sent with ATN asserted, bytes are transferred
+//! LSB first with a CLK/DATA handshake, and the last byte is flagged with EOI.
+//! Errors are reported with the same `StatusFlags` as the KERNAL's `ST` variable.
+//!
+//! A line is _asserted_ when pulled low. Output bits of port A assert the lines,
+//! while input bits read high when a line is released. Timing uses CIA1 timer B,
+//! counting cycles that are close to microseconds, and interrupts are disabled
+//! during each byte.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::iec::Iec;
+//! use mos_hardware::cbm_kernal::Device;
+//!
+//! let mut iec = Iec::new();
+//! let mut buffer = [0u8; 4096];
+//! let (address, length) = iec.load(Device::Drive8, b"GAME.DAT", &mut buffer)?;
+//! iec.save(Device::Drive8, b"@0:SAVEGAME", 0xc000, &buffer[..length])?;
+//! ~~~
+
+use super::{cia1_control, cia2};
+use crate::cbm_kernal::{Device, StatusFlags};
+use crate::cia::{CIA2PortA, Timer, TimerMode};
+use crate::interrupt;
+
+/// Command sent under ATN: listen, add device number
+const LISTEN: u8 = 0x20;
+/// Command sent under ATN: talk, add device number
+const TALK: u8 = 0x40;
+/// Command sent under ATN: all devices stop listening
+const UNLISTEN: u8 = 0x3f;
+/// Command sent under ATN: all devices stop talking
+const UNTALK: u8 = 0x5f;
+/// Secondary address for data, add channel
+const SECOND: u8 = 0x60;
+/// Secondary address to close a channel, add channel
+const CLOSE: u8 = 0xe0;
+/// Secondary address to open a channel, add channel; followed by the file name
+const OPEN: u8 = 0xf0;
+
+/// Time for a device to respond to ATN
+const ATN_RESPONSE_MICROS: u32 = 1000;
+/// Time for a listener to acknowledge a byte
+const FRAME_ACK_MICROS: u32 = 1000;
+/// Time without the talker asserting CLK that signals EOI
+const EOI_MICROS: u32 = 200;
+/// Time for a listener to hold DATA when acknowledging EOI
+const EOI_ACK_MICROS: u16 = 60;
+/// Time for each half of a bit
+const BIT_MICROS: u16 = 60;
+/// Time for a listener to become ready or a talker to send the next byte, _e.g._ while seeking
+const READY_MICROS: u32 = 2_000_000;
+/// Time for the talker to clock each bit
+const BIT_TIMEOUT_MICROS: u32 = 1000;
+
+/// Poll `ready` until it returns true or about `micros` have passed
+fn wait_for<F: FnMut() -> bool>(micros: u32, mut ready: F) -> bool {
+    let timer = cia1_control();
+    let mut remaining = micros;
+    loop {
+        let chunk = remaining.min(u16::MAX as u32);
+        timer.start_timer(Timer::B, chunk as u16, TimerMode::OneShot);
+        while timer.is_timer_running(Timer::B) {
+            if ready() {
+                return true;
+            }
+        }
+        remaining -= chunk;
+        if remaining == 0 {
+            return ready();
+        }
+    }
+}
+
+/// Busy wait for about `micros`
+fn delay(micros: u16) {
+    let timer = cia1_control();
+    timer.start_timer(Timer::B, micros, TimerMode::OneShot);
+    timer.wait_timer(Timer::B);
+}
+
+/// Assert (pull low) or release output `line` of CIA2 port A
+fn set_line(line: CIA2PortA, asserted: bool) {
+    unsafe {
+        cia2().port_a.modify(|mut v| {
+            v.set(line, asserted);
+            v
+        });
+    }
+}
+
+fn set_atn(asserted: bool) {
+    set_line(CIA2PortA::ATN_OUT, asserted);
+}
+
+fn set_clock(asserted: bool) {
+    set_line(CIA2PortA::CLOCK_OUT, asserted);
+}
+
+fn set_data(asserted: bool) {
+    set_line(CIA2PortA::DATA_OUT, asserted);
+}
+
+/// True if CLK is asserted by any device
+fn clock_asserted() -> bool {
+    !cia2().port_a.read().contains(CIA2PortA::CLOCK_IN)
+}
+
+/// True if DATA is asserted by any device
+fn data_asserted() -> bool {
+    !cia2().port_a.read().contains(CIA2PortA::DATA_IN)
+}
+
+/// Serial bus controller with the accumulated status of the last operations
+pub struct Iec {
+    status: StatusFlags,
+}
+
+impl Iec {
+    /// New bus controller; the bus lines are not touched until the first command
+    pub const fn new() -> Self {
+        Self {
+            status: StatusFlags::empty(),
+        }
+    }
+
+    /// Status flags accumulated since the last call, like the KERNAL's `READST`
+    pub fn read_status(&mut self) -> StatusFlags {
+        core::mem::replace(&mut self.status, StatusFlags::empty())
+    }
+
+    /// Record `flags` in the status and return them as error
+    fn fail<T>(&mut self, flags: StatusFlags) -> Result<T, StatusFlags> {
+        self.status |= flags;
+        Err(flags)
+    }
+
+    /// Device number of a drive (8-11)
+    fn drive_number(&mut self, device: Device) -> Result<u8, StatusFlags> {
+        match device.value() {
+            number @ 8..=11 => Ok(number),
+            _ => self.fail(StatusFlags::DEVICE_NOT_PRESENT),
+        }
+    }
+
+    /// Send `byte` as talker; CLK must be asserted on entry and is asserted on return
+    fn send_byte(&mut self, byte: u8, eoi: bool) -> Result<(), StatusFlags> {
+        interrupt::free(|| {
+            if !data_asserted() {
+                return self.fail(StatusFlags::DEVICE_NOT_PRESENT);
+            }
+            // ready to send; wait for all listeners to be ready for data
+            set_clock(false);
+            if !wait_for(READY_MICROS, || !data_asserted()) {
+                return self.fail(StatusFlags::WRITE_TIME_OUT);
+            }
+            if eoi {
+                // listeners acknowledge EOI by pulsing DATA
+                if !wait_for(READY_MICROS, data_asserted)
+                    || !wait_for(READY_MICROS, || !data_asserted())
+                {
+                    return self.fail(StatusFlags::WRITE_TIME_OUT);
+                }
+            }
+            set_clock(true);
+            let mut bits = byte;
+            for _ in 0..8 {
+                // a released DATA line is a one
+                set_data(bits & 1 == 0);
+                bits >>= 1;
+                delay(BIT_MICROS / 3);
+                set_clock(false);
+                delay(BIT_MICROS);
+                set_clock(true);
+                set_data(false);
+            }
+            match wait_for(FRAME_ACK_MICROS, data_asserted) {
+                true => Ok(()),
+                false => self.fail(StatusFlags::WRITE_TIME_OUT),
+            }
+        })
+    }
+
+    /// Receive a byte as listener and return it with the EOI flag
+    ///
+    /// DATA must be asserted on entry and is asserted on return.
+    fn receive_byte(&mut self) -> Result<(u8, bool), StatusFlags> {
+        interrupt::free(|| {
+            // talker ready to send
+            if !wait_for(READY_MICROS, || !clock_asserted()) {
+                return self.fail(StatusFlags::READ_TIME_OUT);
+            }
+            // ready for data
+            set_data(false);
+            let mut eoi = false;
+            if !wait_for(EOI_MICROS, clock_asserted) {
+                // acknowledge EOI
+                eoi = true;
+                set_data(true);
+                delay(EOI_ACK_MICROS);
+                set_data(false);
+                if !wait_for(EOI_MICROS * 5, clock_asserted) {
+                    return self.fail(StatusFlags::READ_TIME_OUT);
+                }
+            }
+            let mut byte = 0u8;
+            for _ in 0..8 {
+                if !wait_for(BIT_TIMEOUT_MICROS, || !clock_asserted()) {
+                    return self.fail(StatusFlags::READ_TIME_OUT);
+                }
+                byte = (byte >> 1) | if data_asserted() { 0 } else { 0x80 };
+                if !wait_for(BIT_TIMEOUT_MICROS, clock_asserted) {
+                    return self.fail(StatusFlags::READ_TIME_OUT);
+                }
+            }
+            set_data(true);
+            if eoi {
+                self.status |= StatusFlags::END_OF_IDENTITY;
+            }
+            Ok((byte, eoi))
+        })
+    }
+
+    /// Assert ATN and send command bytes
+    fn command(&mut self, bytes: &[u8]) -> Result<(), StatusFlags> {
+        set_atn(true);
+        set_clock(true);
+        set_data(false);
+        if !wait_for(ATN_RESPONSE_MICROS, data_asserted) {
+            set_atn(false);
+            set_clock(false);
+            return self.fail(StatusFlags::DEVICE_NOT_PRESENT);
+        }
+        for byte in bytes {
+            if let Err(flags) = self.send_byte(*byte, false) {
+                self.release_bus();
+                return Err(flags);
+            }
+        }
+        Ok(())
+    }
+
+    /// Release ATN, CLK and DATA
+    fn release_bus(&mut self) {
+        set_atn(false);
+        set_clock(false);
+        set_data(false);
+    }
+
+    /// Command `device` to listen on `channel` (0-15)
+    pub fn listen(&mut self, device: Device, channel: u8) -> Result<(), StatusFlags> {
+        let number = self.drive_number(device)?;
+        self.command(&[LISTEN | number, SECOND | (channel & 0x0f)])?;
+        set_atn(false);
+        Ok(())
+    }
+
+    /// Command `device` to talk on `channel` (0-15) and turn around the bus
+    pub fn talk(&mut self, device: Device, channel: u8) -> Result<(), StatusFlags> {
+        let number = self.drive_number(device)?;
+        self.command(&[TALK | number, SECOND | (channel & 0x0f)])?;
+        // become listener while the device becomes talker
+        set_data(true);
+        set_atn(false);
+        set_clock(false);
+        match wait_for(ATN_RESPONSE_MICROS, clock_asserted) {
+            true => Ok(()),
+            false => {
+                self.release_bus();
+                self.fail(StatusFlags::READ_TIME_OUT)
+            }
+        }
+    }
+
+    /// Command all listeners to stop listening
+    pub fn unlisten(&mut self) -> Result<(), StatusFlags> {
+        let result = self.command(&[UNLISTEN]);
+        self.release_bus();
+        result
+    }
+
+    /// Command all talkers to stop talking
+    pub fn untalk(&mut self) -> Result<(), StatusFlags> {
+        let result = self.command(&[UNTALK]);
+        self.release_bus();
+        result
+    }
+
+    /// Send bytes to listening devices; the last byte is flagged with EOI if `eoi` is set
+    pub fn send(&mut self, bytes: &[u8], eoi: bool) -> Result<(), StatusFlags> {
+        let last = bytes.len().saturating_sub(1);
+        bytes
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, byte)| self.send_byte(*byte, eoi && i == last))
+    }
+
+    /// Receive a byte from the talking device and return it with the EOI flag
+    pub fn receive(&mut self) -> Result<(u8, bool), StatusFlags> {
+        self.receive_byte()
+    }
+
+    /// Open file `name` on `channel` of `device`
+    pub fn open(&mut self, device: Device, channel: u8, name: &[u8]) -> Result<(), StatusFlags> {
+        let number = self.drive_number(device)?;
+        self.command(&[LISTEN | number, OPEN | (channel & 0x0f)])?;
+        set_atn(false);
+        let sent = self.send(name, true);
+        let unlistened = self.unlisten();
+        sent.and(unlistened)
+    }
+
+    /// Close `channel` of `device`
+    pub fn close(&mut self, device: Device, channel: u8) -> Result<(), StatusFlags> {
+        let number = self.drive_number(device)?;
+        self.command(&[LISTEN | number, CLOSE | (channel & 0x0f)])?;
+        self.unlisten()
+    }
+
+    /// Load file `name` into `destination`, returning its load address and length
+    ///
+    /// As with the KERNAL, a missing file is reported as `READ_TIME_OUT`.
+    /// Fails with `READ_ERROR` if the file does not fit in `destination`.
+    pub fn load(
+        &mut self,
+        device: Device,
+        name: &[u8],
+        destination: &mut [u8],
+    ) -> Result<(u16, usize), StatusFlags> {
+        self.open(device, 0, name)?;
+        self.talk(device, 0)?;
+        let result = self.receive_program(destination);
+        let untalked = self.untalk();
+        let closed = self.close(device, 0);
+        let (address, length) = result?;
+        untalked.and(closed)?;
+        Ok((address, length))
+    }
+
+    /// Receive load address and data until EOI
+    fn receive_program(&mut self, destination: &mut [u8]) -> Result<(u16, usize), StatusFlags> {
+        let (low, _) = self.receive_byte()?;
+        let (high, mut eoi) = self.receive_byte()?;
+        let address = u16::from_le_bytes([low, high]);
+        let mut length = 0;
+        while !eoi {
+            let (byte, last) = self.receive_byte()?;
+            match destination.get_mut(length) {
+                Some(target) => *target = byte,
+                None => return self.fail(StatusFlags::READ_ERROR),
+            }
+            length += 1;
+            eoi = last;
+        }
+        Ok((address, length))
+    }
+
+    /// Save `data` as file `name` with load `address`
+    ///
+    /// Prefix the name with `@0:` to replace an existing file.
+    pub fn save(
+        &mut self,
+        device: Device,
+        name: &[u8],
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), StatusFlags> {
+        self.open(device, 1, name)?;
+        let result = self.listen(device, 1).and_then(|_| {
+            let [low, high] = address.to_le_bytes();
+            self.send(&[low, high], data.is_empty())?;
+            self.send(data, true)
+        });
+        let unlistened = self.unlisten();
+        let closed = self.close(device, 1);
+        result.and(unlistened).and(closed)
+    }
+}
+
+impl Default for Iec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
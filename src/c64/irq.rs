@@ -0,0 +1,498 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Interrupt handling in pure Rust
+//!
+//! Small assembler trampolines are installed in the IRQ and NMI vectors. They save the
+//! CPU registers and the caller-saved llvm-mos zero page registers (`__rc2`-`__rc19`),
+//! call a Rust dispatcher, and restore everything before returning. The dispatcher
+//! acknowledges the VIC-II and CIA interrupt sources and calls the handler registered
+//! for each source that fired. Handlers can be functions or closures.
+//!
+//! Two vector setups are supported:
+//!
+//! - `Vectors::Kernal` installs the trampolines in the RAM vectors at `$0314` (IRQ) and
+//!   `$0318` (NMI). BASIC and KERNAL stay banked in and, whenever CIA1 has
+//!   requested an interrupt, the previous IRQ handler is chained so that the
+//!   jiffy clock and keyboard scanning keep running.
+//! - `Vectors::Hardware` banks out BASIC and KERNAL and installs the trampolines
+//!   directly in the hardware vectors at `$fffe` (IRQ) and `$fffa` (NMI).
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::irq::{self, Interrupt, Vectors};
+//!
+//! irq::install(Vectors::Hardware);
+//! c64::vic2().set_raster_irq_line(280); // lines above 255 are reachable
+//! let mut frames: u8 = 0;
+//! irq::set_handler(Interrupt::Raster, move || {
+//!     frames = frames.wrapping_add(1);
+//!     unsafe { c64::vic2().border_color.write(frames & 0x0f) };
+//! });
+//! irq::set_handler(Interrupt::Restore, || unsafe {
+//!     c64::vic2().border_color.write(vic2::BLACK);
+//! });
+//! loop {}
+//! ~~~
+//!
+//! # Handlers
+//!
+//! Handlers run with interrupts disabled and should be short. Do not register or
+//! remove the handler of a source from within that same handler. While a handler is
+//! replaced its source is disabled, and as the RESTORE key cannot be masked, a press
+//! in that window is ignored. The sprite collision interrupts only fire again once
+//! the corresponding collision register has been read.
+
+use super::cpu6510::{CpuPortFlags, CPU_PORT};
+use super::{cia1, cia2, vic2};
+use crate::bus::RW;
+use crate::cia::InterruptControl;
+use crate::interrupt;
+use crate::vic2::{IRQEnableFlags, InterruptFlags};
+use alloc::boxed::Box;
+use core::ptr::{addr_of, addr_of_mut};
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Hardware IRQ vector (0xfffe), used when the KERNAL is banked out
+pub const IRQ_VECTOR: *const RW<u16> = (0xfffe) as _;
+
+/// Hardware NMI vector (0xfffa), used when the KERNAL is banked out
+pub const NMI_VECTOR: *const RW<u16> = (0xfffa) as _;
+
+/// KERNAL IRQ vector in RAM, `CINV` (0x0314)
+pub const KERNAL_IRQ_VECTOR: *const RW<u16> = (0x0314) as _;
+
+/// KERNAL NMI vector in RAM, `NMINV` (0x0318)
+pub const KERNAL_NMI_VECTOR: *const RW<u16> = (0x0318) as _;
+
+/// KERNAL routine that restores A, X, Y from the stack and returns from interrupt
+const KERNAL_IRQ_RETURN: u16 = 0xea81;
+
+/// Where to install the interrupt trampolines
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Vectors {
+    /// Keep BASIC and KERNAL; hook the RAM vectors at `$0314` and `$0318`
+    Kernal,
+    /// Bank out BASIC and KERNAL; hook the hardware vectors at `$fffe` and `$fffa`
+    Hardware,
+}
+
+/// Interrupt sources that can be given a handler
+///
+/// VIC-II and CIA1 sources are delivered through IRQ; CIA2 and the RESTORE key
+/// through NMI.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interrupt {
+    /// VIC-II raster compare
+    Raster = 0,
+    /// VIC-II first collision of a sprite with background
+    SpriteBackgroundCollision = 1,
+    /// VIC-II first collision of a sprite with another sprite
+    SpriteSpriteCollision = 2,
+    /// VIC-II light pen triggered
+    LightPen = 3,
+    /// CIA1 timer A underflow
+    Cia1TimerA = 4,
+    /// CIA1 timer B underflow
+    Cia1TimerB = 5,
+    /// CIA1 time-of-day alarm
+    Cia1TodAlarm = 6,
+    /// CIA1 serial shift register complete
+    Cia1Serial = 7,
+    /// CIA1 `FLAG` pin (cassette read, serial SRQ)
+    Cia1Flag = 8,
+    /// CIA2 timer A underflow (NMI)
+    Cia2TimerA = 9,
+    /// CIA2 timer B underflow (NMI)
+    Cia2TimerB = 10,
+    /// CIA2 time-of-day alarm (NMI)
+    Cia2TodAlarm = 11,
+    /// CIA2 serial shift register complete (NMI)
+    Cia2Serial = 12,
+    /// CIA2 `FLAG` pin, _e.g._ RS-232 receive (NMI)
+    Cia2Flag = 13,
+    /// RESTORE key (NMI)
+    Restore = 14,
+}
+
+/// Number of interrupt sources
+const SOURCES: usize = 15;
+
+/// Sources of the VIC-II
+const VIC_SOURCES: [Interrupt; 4] = [
+    Interrupt::Raster,
+    Interrupt::SpriteBackgroundCollision,
+    Interrupt::SpriteSpriteCollision,
+    Interrupt::LightPen,
+];
+
+/// Sources of CIA1 and CIA2
+const CIA_SOURCES: [Interrupt; 10] = [
+    Interrupt::Cia1TimerA,
+    Interrupt::Cia1TimerB,
+    Interrupt::Cia1TodAlarm,
+    Interrupt::Cia1Serial,
+    Interrupt::Cia1Flag,
+    Interrupt::Cia2TimerA,
+    Interrupt::Cia2TimerB,
+    Interrupt::Cia2TodAlarm,
+    Interrupt::Cia2Serial,
+    Interrupt::Cia2Flag,
+];
+
+impl Interrupt {
+    /// Enable or disable the source in the VIC-II or CIA interrupt mask
+    fn set_enabled(self, enable: bool) {
+        let source = self as u8;
+        unsafe {
+            match source {
+                0..=3 => {
+                    let flag = IRQEnableFlags::from_bits_truncate(1 << source);
+                    vic2().irq_enable.modify(|mut r| {
+                        r.set(flag, enable);
+                        r
+                    });
+                }
+                4..=8 => cia1()
+                    .control
+                    .interrupt
+                    .write(cia_mask(source - Self::Cia1TimerA as u8, enable)),
+                9..=13 => cia2()
+                    .control
+                    .interrupt
+                    .write(cia_mask(source - Self::Cia2TimerA as u8, enable)),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// CIA interrupt control value that sets or clears a single source
+const fn cia_mask(bit: u8, enable: bool) -> InterruptControl {
+    let mask = InterruptControl::from_bits_truncate(1 << bit);
+    match enable {
+        true => mask.union(InterruptControl::SET_CLEAR),
+        false => mask,
+    }
+}
+
+type Handler = Option<Box<dyn FnMut()>>;
+
+const NO_HANDLER: Handler = None;
+
+/// Registered handlers indexed by `Interrupt`
+static mut HANDLERS: [Handler; SOURCES] = [NO_HANDLER; SOURCES];
+
+/// Set while the dispatchers may call the handler of a source
+///
+/// `interrupt::free()` does not hold off NMIs, so the slot in `HANDLERS` is only
+/// replaced while this is cleared. A single byte is written atomically.
+static mut ARMED: [bool; SOURCES] = [false; SOURCES];
+
+/// Vector setup and values replaced by `install()`
+struct Saved {
+    vectors: Vectors,
+    irq: u16,
+    nmi: u16,
+    cpu_port: CpuPortFlags,
+}
+
+static mut SAVED: Option<Saved> = None;
+
+/// Previous KERNAL IRQ vector; aligned to avoid the 6502 `jmp ($xxff)` bug
+#[repr(C, align(2))]
+struct ChainVector(u16);
+
+static mut KERNAL_IRQ_CHAIN: ChainVector = ChainVector(KERNAL_IRQ_RETURN);
+
+/// Set by the IRQ dispatcher when the KERNAL IRQ handler should run
+static mut CHAIN_TO_KERNAL: bool = false;
+
+extern "C" {
+    /// IRQ trampoline for the hardware vector
+    fn mos_hardware_c64_irq();
+    /// IRQ trampoline for the KERNAL vector
+    fn mos_hardware_c64_kernal_irq();
+    /// NMI trampoline for both the hardware and KERNAL vectors
+    fn mos_hardware_c64_nmi();
+}
+
+/// Install interrupt trampolines in either the KERNAL or hardware vectors
+///
+/// Sources with a registered handler are enabled. With `Vectors::Hardware`, BASIC and
+/// KERNAL are banked out and all other CIA interrupts are disabled. Calling `install()`
+/// again first undoes the previous installation.
+pub fn install(vectors: Vectors) {
+    interrupt::free(|| unsafe {
+        restore();
+        let cpu_port = (*CPU_PORT).read();
+        let (irq, nmi) = match vectors {
+            Vectors::Kernal => {
+                let saved = ((*KERNAL_IRQ_VECTOR).read(), (*KERNAL_NMI_VECTOR).read());
+                KERNAL_IRQ_CHAIN = ChainVector(saved.0);
+                (*KERNAL_IRQ_VECTOR)
+                    .write(mos_hardware_c64_kernal_irq as *const () as usize as u16);
+                (*KERNAL_NMI_VECTOR).write(mos_hardware_c64_nmi as *const () as usize as u16);
+                saved
+            }
+            Vectors::Hardware => {
+                for cia in [&cia1().control, &cia2().control] {
+                    cia.interrupt.write(InterruptControl::DISABLE_ALL);
+                }
+                acknowledge_cia1();
+                acknowledge_cia2();
+                (*CPU_PORT).write(CpuPortFlags::RAM_IO_RAM);
+                let saved = ((*IRQ_VECTOR).read(), (*NMI_VECTOR).read());
+                (*IRQ_VECTOR).write(mos_hardware_c64_irq as *const () as usize as u16);
+                (*NMI_VECTOR).write(mos_hardware_c64_nmi as *const () as usize as u16);
+                saved
+            }
+        };
+        VIC_SOURCES
+            .iter()
+            .chain(CIA_SOURCES.iter())
+            .filter(|source| (*addr_of_mut!(HANDLERS))[**source as usize].is_some())
+            .for_each(|source| source.set_enabled(true));
+        SAVED = Some(Saved {
+            vectors,
+            irq,
+            nmi,
+            cpu_port,
+        });
+    });
+}
+
+/// Restore the vectors and memory configuration replaced by `install()`
+///
+/// Registered handlers are kept, but their sources are disabled so that nothing
+/// fires into the restored vectors. Does nothing if not installed.
+pub fn uninstall() {
+    interrupt::free(|| unsafe { restore() });
+}
+
+/// Undo `install()`; must be called with interrupts disabled
+unsafe fn restore() {
+    let Some(saved) = (*addr_of_mut!(SAVED)).take() else {
+        return;
+    };
+    vic2().irq_enable.write(IRQEnableFlags::empty());
+    vic2().irq_status.write(InterruptFlags::all());
+    CIA_SOURCES
+        .iter()
+        .filter(|source| (*addr_of_mut!(HANDLERS))[**source as usize].is_some())
+        .for_each(|source| source.set_enabled(false));
+    acknowledge_cia1();
+    acknowledge_cia2();
+    match saved.vectors {
+        Vectors::Kernal => {
+            (*KERNAL_IRQ_VECTOR).write(saved.irq);
+            (*KERNAL_NMI_VECTOR).write(saved.nmi);
+        }
+        Vectors::Hardware => {
+            (*IRQ_VECTOR).write(saved.irq);
+            (*NMI_VECTOR).write(saved.nmi);
+            (*CPU_PORT).write(saved.cpu_port);
+        }
+    }
+    // KERNAL jiffy clock and keyboard interrupt
+    Interrupt::Cia1TimerA.set_enabled(true);
+}
+
+/// Register `handler` for `source` and enable the source in the VIC-II or CIA
+///
+/// Any previous handler for the source is dropped.
+/// Note that a CIA timer must also be started for it to generate interrupts.
+///
+/// # Examples
+/// ~~~
+/// irq::set_handler(Interrupt::SpriteSpriteCollision, || {
+///     let sprites = c64::vic2().sprite_sprite_collision.read();
+///     // ...
+/// });
+/// ~~~
+pub fn set_handler<F: FnMut() + 'static>(source: Interrupt, handler: F) {
+    replace_handler(source, Some(Box::new(handler)));
+}
+
+/// Disable `source` and drop its handler
+pub fn remove_handler(source: Interrupt) {
+    replace_handler(source, None);
+}
+
+/// Disable and disarm `source` while its slot is replaced, then enable it if `handler` is set
+///
+/// The RESTORE key cannot be masked; a press while its handler is replaced is ignored.
+fn replace_handler(source: Interrupt, handler: Handler) {
+    let enable = handler.is_some();
+    interrupt::free(|| unsafe {
+        source.set_enabled(false);
+        let armed = addr_of_mut!(ARMED[source as usize]);
+        armed.write_volatile(false);
+        compiler_fence(Ordering::SeqCst);
+        (*addr_of_mut!(HANDLERS))[source as usize] = handler;
+        if enable {
+            compiler_fence(Ordering::SeqCst);
+            armed.write_volatile(true);
+            source.set_enabled(true);
+        }
+    });
+}
+
+/// Read and acknowledge pending VIC-II interrupts
+pub fn acknowledge_vic() -> InterruptFlags {
+    let flags = vic2().irq_status.read();
+    unsafe { vic2().irq_status.write(flags) };
+    flags
+}
+
+/// Read and acknowledge pending CIA1 interrupts
+///
+/// Reading the interrupt control register clears it.
+pub fn acknowledge_cia1() -> InterruptControl {
+    cia1().control.interrupt.read()
+}
+
+/// Read and acknowledge pending CIA2 interrupts
+///
+/// Reading the interrupt control register clears it.
+pub fn acknowledge_cia2() -> InterruptControl {
+    cia2().control.interrupt.read()
+}
+
+/// Call handler of `source`, if any
+#[inline(always)]
+fn call(source: usize) {
+    if !unsafe { addr_of!(ARMED[source]).read_volatile() } {
+        return;
+    }
+    if let Some(handler) = unsafe { (*addr_of_mut!(HANDLERS))[source].as_mut() } {
+        handler();
+    }
+}
+
+/// Call handlers of the five CIA sources in `flags`, starting at `first`
+#[inline(always)]
+fn call_cia(flags: InterruptControl, first: Interrupt) {
+    (0..5)
+        .filter(|bit| flags.bits() & (1 << bit) != 0)
+        .for_each(|bit| call(first as usize + bit));
+}
+
+#[cfg_attr(not(target_arch = "mos"), allow(dead_code))]
+extern "C" fn dispatch_irq() {
+    let vic = acknowledge_vic();
+    (0..4)
+        .filter(|bit| vic.bits() & (1 << bit) != 0)
+        .for_each(call);
+    let cia = acknowledge_cia1();
+    call_cia(cia, Interrupt::Cia1TimerA);
+    unsafe { CHAIN_TO_KERNAL = cia.contains(InterruptControl::IRQ) };
+}
+
+#[cfg_attr(not(target_arch = "mos"), allow(dead_code))]
+extern "C" fn dispatch_nmi() {
+    let cia = acknowledge_cia2();
+    match cia.contains(InterruptControl::IRQ) {
+        true => call_cia(cia, Interrupt::Cia2TimerA),
+        false => call(Interrupt::Restore as usize),
+    }
+}
+
+// The caller-saved zero page registers `__rc2`-`__rc19` are consecutive and
+// are pushed on the hardware stack so that nested interrupts (NMI during IRQ) work.
+#[cfg(target_arch = "mos")]
+core::arch::global_asm!(
+    ".section .text.mos_hardware_c64_irq,\"ax\",@progbits",
+    ".globl mos_hardware_c64_irq",
+    "mos_hardware_c64_irq:",
+    "pha",
+    "txa",
+    "pha",
+    "tya",
+    "pha",
+    "cld",
+    "ldx #0",
+    "2: lda __rc2,x",
+    "pha",
+    "inx",
+    "cpx #18",
+    "bne 2b",
+    "jsr {dispatch_irq}",
+    "ldx #17",
+    "3: pla",
+    "sta __rc2,x",
+    "dex",
+    "bpl 3b",
+    "pla",
+    "tay",
+    "pla",
+    "tax",
+    "pla",
+    "rti",
+    "",
+    // KERNAL has already pushed A, X, Y
+    ".section .text.mos_hardware_c64_kernal_irq,\"ax\",@progbits",
+    ".globl mos_hardware_c64_kernal_irq",
+    "mos_hardware_c64_kernal_irq:",
+    "cld",
+    "ldx #0",
+    "2: lda __rc2,x",
+    "pha",
+    "inx",
+    "cpx #18",
+    "bne 2b",
+    "jsr {dispatch_irq}",
+    "ldx #17",
+    "3: pla",
+    "sta __rc2,x",
+    "dex",
+    "bpl 3b",
+    "lda {chain_to_kernal}",
+    "beq 4f",
+    "jmp ({kernal_irq_chain})",
+    "4: jmp {kernal_irq_return}",
+    "",
+    ".section .text.mos_hardware_c64_nmi,\"ax\",@progbits",
+    ".globl mos_hardware_c64_nmi",
+    "mos_hardware_c64_nmi:",
+    "pha",
+    "txa",
+    "pha",
+    "tya",
+    "pha",
+    "cld",
+    "ldx #0",
+    "2: lda __rc2,x",
+    "pha",
+    "inx",
+    "cpx #18",
+    "bne 2b",
+    "jsr {dispatch_nmi}",
+    "ldx #17",
+    "3: pla",
+    "sta __rc2,x",
+    "dex",
+    "bpl 3b",
+    "pla",
+    "tay",
+    "pla",
+    "tax",
+    "pla",
+    "rti",
+    dispatch_irq = sym dispatch_irq,
+    dispatch_nmi = sym dispatch_nmi,
+    chain_to_kernal = sym CHAIN_TO_KERNAL,
+    kernal_irq_chain = sym KERNAL_IRQ_CHAIN,
+    kernal_irq_return = const KERNAL_IRQ_RETURN,
+);
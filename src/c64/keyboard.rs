@@ -0,0 +1,511 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Keyboard matrix scanner that does not need the KERNAL
+//!
+//! The 64 keys are wired as an 8x8 matrix: a column is selected by pulling
+//! one bit of CIA1 port A low, and pressed keys in that column pull the
+//! corresponding bits of port B low. RESTORE is not part of the matrix but
+//! triggers an NMI, see `enable_restore()`.
+//!
+//! `Keyboard::scan()` is intended to be called once per frame, _e.g._ from a
+//! raster interrupt, and queues key-down and key-up events that can be read with
+//! `Keyboard::poll()`. Scans that show ghost keys are ignored, and a change must be
+//! seen in a number of consecutive scans before it is accepted (debouncing).
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::keyboard::{Key, KeyEvent, Keyboard};
+//!
+//! static mut KEYBOARD: Keyboard = Keyboard::new();
+//!
+//! c64::hardware_raster_irq(250, || unsafe { KEYBOARD.scan() });
+//! loop {
+//!     match interrupt::free(|| unsafe { KEYBOARD.poll() }) {
+//!         Some(KeyEvent::Down(Key::F1, _)) => { /* ... */ }
+//!         Some(KeyEvent::Down(key, modifiers)) => {
+//!             let petscii = key.to_petscii(modifiers);
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ~~~
+
+use super::cia1;
+use super::irq::{self, Interrupt};
+use crate::cia::{CIA1DirectionA, CIA1DirectionB, CIA1PortA, KeyboardRow};
+use bitflags::bitflags;
+use core::ptr::{addr_of, addr_of_mut};
+
+/// Key position in the matrix; the value is `8 * column + row`
+#[repr(u8)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Key {
+    InstDel = 0,
+    Return,
+    CursorRight,
+    F7,
+    F1,
+    F3,
+    F5,
+    CursorDown,
+    Num3 = 8,
+    W,
+    A,
+    Num4,
+    Z,
+    S,
+    E,
+    LeftShift,
+    Num5 = 16,
+    R,
+    D,
+    Num6,
+    C,
+    F,
+    T,
+    X,
+    Num7 = 24,
+    Y,
+    G,
+    Num8,
+    B,
+    H,
+    U,
+    V,
+    Num9 = 32,
+    I,
+    J,
+    Num0,
+    M,
+    K,
+    O,
+    N,
+    Plus = 40,
+    P,
+    L,
+    Minus,
+    Period,
+    Colon,
+    At,
+    Comma,
+    Pound = 48,
+    Asterisk,
+    Semicolon,
+    ClrHome,
+    RightShift,
+    Equal,
+    ArrowUp,
+    Slash,
+    Num1 = 56,
+    ArrowLeft,
+    Ctrl,
+    Num2,
+    Space,
+    Commodore,
+    Q,
+    RunStop,
+    /// Not part of the matrix; reported via NMI
+    Restore = 64,
+}
+
+/// All matrix keys ordered by `8 * column + row`
+const MATRIX_KEYS: [Key; 64] = {
+    use Key::*;
+    [
+        InstDel,
+        Return,
+        CursorRight,
+        F7,
+        F1,
+        F3,
+        F5,
+        CursorDown,
+        Num3,
+        W,
+        A,
+        Num4,
+        Z,
+        S,
+        E,
+        LeftShift,
+        Num5,
+        R,
+        D,
+        Num6,
+        C,
+        F,
+        T,
+        X,
+        Num7,
+        Y,
+        G,
+        Num8,
+        B,
+        H,
+        U,
+        V,
+        Num9,
+        I,
+        J,
+        Num0,
+        M,
+        K,
+        O,
+        N,
+        Plus,
+        P,
+        L,
+        Minus,
+        Period,
+        Colon,
+        At,
+        Comma,
+        Pound,
+        Asterisk,
+        Semicolon,
+        ClrHome,
+        RightShift,
+        Equal,
+        ArrowUp,
+        Slash,
+        Num1,
+        ArrowLeft,
+        Ctrl,
+        Num2,
+        Space,
+        Commodore,
+        Q,
+        RunStop,
+    ]
+};
+
+/// Unshifted PETSCII codes by matrix position; zero for modifier keys
+const PETSCII_UNSHIFTED: [u8; 64] = [
+    0x14, 0x0d, 0x1d, 0x88, 0x85, 0x86, 0x87, 0x11, // column 0
+    0x33, 0x57, 0x41, 0x34, 0x5a, 0x53, 0x45, 0x00, // column 1
+    0x35, 0x52, 0x44, 0x36, 0x43, 0x46, 0x54, 0x58, // column 2
+    0x37, 0x59, 0x47, 0x38, 0x42, 0x48, 0x55, 0x56, // column 3
+    0x39, 0x49, 0x4a, 0x30, 0x4d, 0x4b, 0x4f, 0x4e, // column 4
+    0x2b, 0x50, 0x4c, 0x2d, 0x2e, 0x3a, 0x40, 0x2c, // column 5
+    0x5c, 0x2a, 0x3b, 0x13, 0x00, 0x3d, 0x5e, 0x2f, // column 6
+    0x31, 0x5f, 0x00, 0x32, 0x20, 0x00, 0x51, 0x03, // column 7
+];
+
+/// Shifted PETSCII codes by matrix position; zero for modifier keys
+const PETSCII_SHIFTED: [u8; 64] = [
+    0x94, 0x8d, 0x9d, 0x8c, 0x89, 0x8a, 0x8b, 0x91, // column 0
+    0x23, 0xd7, 0xc1, 0x24, 0xda, 0xd3, 0xc5, 0x00, // column 1
+    0x25, 0xd2, 0xc4, 0x26, 0xc3, 0xc6, 0xd4, 0xd8, // column 2
+    0x27, 0xd9, 0xc7, 0x28, 0xc2, 0xc8, 0xd5, 0xd6, // column 3
+    0x29, 0xc9, 0xca, 0x30, 0xcd, 0xcb, 0xcf, 0xce, // column 4
+    0xdb, 0xd0, 0xcc, 0xdd, 0x3e, 0x5b, 0xba, 0x3c, // column 5
+    0xa9, 0xc0, 0x5d, 0x93, 0x00, 0x3d, 0xde, 0x3f, // column 6
+    0x21, 0x5f, 0x00, 0x22, 0xa0, 0x00, 0xd1, 0x83, // column 7
+];
+
+impl Key {
+    /// Key at matrix `column` (port A bit) and `row` (port B bit)
+    pub const fn from_matrix(column: u8, row: u8) -> Self {
+        MATRIX_KEYS[((column & 7) * 8 + (row & 7)) as usize]
+    }
+
+    /// Matrix column (port A bit) of the key
+    pub const fn column(self) -> u8 {
+        (self as u8 >> 3) & 7
+    }
+
+    /// Matrix row (port B bit) of the key
+    pub const fn row(self) -> u8 {
+        self as u8 & 7
+    }
+
+    /// True for SHIFT, C= and CTRL
+    pub const fn is_modifier(self) -> bool {
+        matches!(
+            self,
+            Key::LeftShift | Key::RightShift | Key::Commodore | Key::Ctrl
+        )
+    }
+
+    /// PETSCII code of the key with SHIFT applied as by the KERNAL
+    ///
+    /// Returns `None` for modifier keys and RESTORE. C= and CTRL combinations
+    /// are not decoded.
+    pub const fn to_petscii(self, modifiers: Modifiers) -> Option<u8> {
+        if matches!(self, Key::Restore) {
+            return None;
+        }
+        let code = match modifiers.contains(Modifiers::SHIFT) {
+            true => PETSCII_SHIFTED[self as usize],
+            false => PETSCII_UNSHIFTED[self as usize],
+        };
+        match code {
+            0 => None,
+            _ => Some(code),
+        }
+    }
+}
+
+bitflags! {
+    /// Modifier keys held down
+    #[derive(Default)]
+    pub struct Modifiers: u8 {
+        /// Left or right SHIFT, or SHIFT LOCK
+        const SHIFT     = 0b0000_0001;
+        /// Commodore key
+        const COMMODORE = 0b0000_0010;
+        const CTRL      = 0b0000_0100;
+    }
+}
+
+/// State of all 64 keys; bit `row` of byte `column` is set if the key is pressed
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct KeyMatrix(pub [u8; 8]);
+
+impl KeyMatrix {
+    /// Matrix with no keys pressed
+    pub const fn empty() -> Self {
+        Self([0; 8])
+    }
+
+    /// All keys as a 64-bit bitmap with bit `8 * column + row` set if pressed
+    pub const fn bits(&self) -> u64 {
+        u64::from_le_bytes(self.0)
+    }
+
+    /// True if no keys are pressed
+    pub const fn is_empty(&self) -> bool {
+        self.bits() == 0
+    }
+
+    /// True if `key` is pressed
+    pub const fn is_pressed(&self, key: Key) -> bool {
+        match key {
+            Key::Restore => false,
+            _ => self.0[key.column() as usize] & (1 << key.row()) != 0,
+        }
+    }
+
+    /// Pressed modifier keys
+    pub const fn modifiers(&self) -> Modifiers {
+        let mut modifiers = Modifiers::empty();
+        if self.is_pressed(Key::LeftShift) || self.is_pressed(Key::RightShift) {
+            modifiers = modifiers.union(Modifiers::SHIFT);
+        }
+        if self.is_pressed(Key::Commodore) {
+            modifiers = modifiers.union(Modifiers::COMMODORE);
+        }
+        if self.is_pressed(Key::Ctrl) {
+            modifiers = modifiers.union(Modifiers::CTRL);
+        }
+        modifiers
+    }
+
+    /// True if the matrix may contain ghost keys
+    ///
+    /// When three keys at the corners of a rectangle are pressed, the fourth corner
+    /// reads as pressed too. This happens when two columns share two or more rows.
+    pub fn has_ghosts(&self) -> bool {
+        (0..8).any(|i| (i + 1..8).any(|j| (self.0[i] & self.0[j]).count_ones() >= 2))
+    }
+
+    /// Iterator over pressed keys
+    pub fn pressed(&self) -> impl Iterator<Item = Key> + '_ {
+        MATRIX_KEYS
+            .iter()
+            .copied()
+            .filter(move |key| self.is_pressed(*key))
+    }
+}
+
+/// Read the raw keyboard matrix from CIA1 without debouncing or ghost detection
+///
+/// Rows pulled low by a joystick in port 1 are masked out. Port and data direction
+/// registers are restored afterwards. Should not be interrupted by code that uses CIA1.
+pub fn scan_matrix() -> KeyMatrix {
+    let cia = cia1();
+    let port_a = cia.port_a.read();
+    let direction_a = cia.data_direction_port_a.read();
+    let direction_b = cia.data_direction_port_b.read();
+    let mut matrix = KeyMatrix::empty();
+    unsafe {
+        cia.data_direction_port_a.write(CIA1DirectionA::KEYBOARD);
+        cia.data_direction_port_b.write(CIA1DirectionB::KEYBOARD);
+
+        let read_rows = |columns: u8| {
+            cia.port_a.write(CIA1PortA::from(!columns));
+            KeyboardRow::from(cia.port_b.read()).complement().bits()
+        };
+        // rows active with no column selected come from joystick 1, not the keyboard
+        let joystick = read_rows(0);
+        if read_rows(0xff) & !joystick != 0 {
+            for (column, rows) in matrix.0.iter_mut().enumerate() {
+                *rows = read_rows(1 << column) & !joystick;
+            }
+        }
+
+        cia.port_a.write(port_a);
+        cia.data_direction_port_a.write(direction_a);
+        cia.data_direction_port_b.write(direction_b);
+    }
+    matrix
+}
+
+/// Key-down or key-up event
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KeyEvent {
+    /// Key pressed with the modifiers held at that time
+    Down(Key, Modifiers),
+    /// Key released
+    Up(Key),
+}
+
+/// Number of queued events before new events are dropped
+const QUEUE_SIZE: usize = 16;
+
+/// Set by the RESTORE NMI handler and cleared by `Keyboard::scan()`
+static mut RESTORE_PRESSED: bool = false;
+
+/// Report RESTORE as `Key::Restore` events by registering an NMI handler
+///
+/// Requires `irq::install()`.
+pub fn enable_restore() {
+    irq::set_handler(Interrupt::Restore, || unsafe {
+        addr_of_mut!(RESTORE_PRESSED).write_volatile(true);
+    });
+}
+
+/// Debounced keyboard with an event queue
+pub struct Keyboard {
+    /// Accepted key state
+    stable: KeyMatrix,
+    /// Most recent scan waiting to be accepted
+    candidate: KeyMatrix,
+    /// Number of consecutive scans equal to `candidate`
+    count: u8,
+    /// Number of consecutive equal scans needed to accept a change
+    debounce: u8,
+    events: [Option<KeyEvent>; QUEUE_SIZE],
+    head: u8,
+    len: u8,
+}
+
+impl Keyboard {
+    /// New keyboard accepting changes seen in two consecutive scans
+    pub const fn new() -> Self {
+        Self {
+            stable: KeyMatrix::empty(),
+            candidate: KeyMatrix::empty(),
+            count: 0,
+            debounce: 2,
+            events: [None; QUEUE_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Set the number of consecutive equal scans needed to accept a change (at least 1)
+    pub const fn with_debounce(mut self, scans: u8) -> Self {
+        self.debounce = if scans == 0 { 1 } else { scans };
+        self
+    }
+
+    /// Scan the matrix and queue events for accepted changes
+    ///
+    /// Call at a steady rate, _e.g._ once per frame from a raster interrupt.
+    pub fn scan(&mut self) {
+        let matrix = scan_matrix();
+        if !matrix.has_ghosts() {
+            self.update(matrix);
+        }
+        let restore = unsafe { addr_of!(RESTORE_PRESSED).read_volatile() };
+        if restore {
+            unsafe { addr_of_mut!(RESTORE_PRESSED).write_volatile(false) };
+            self.push(KeyEvent::Down(Key::Restore, self.stable.modifiers()));
+            self.push(KeyEvent::Up(Key::Restore));
+        }
+    }
+
+    /// Debounce `matrix` and queue events if it is accepted
+    fn update(&mut self, matrix: KeyMatrix) {
+        if matrix != self.candidate {
+            self.candidate = matrix;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(1);
+        if self.count < self.debounce || self.candidate == self.stable {
+            return;
+        }
+        let previous = self.stable;
+        self.stable = self.candidate;
+        let modifiers = self.stable.modifiers();
+        for key in MATRIX_KEYS {
+            match (previous.is_pressed(key), self.stable.is_pressed(key)) {
+                (false, true) => self.push(KeyEvent::Down(key, modifiers)),
+                (true, false) => self.push(KeyEvent::Up(key)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Queue `event`; dropped if the queue is full
+    fn push(&mut self, event: KeyEvent) {
+        if (self.len as usize) < QUEUE_SIZE {
+            let index = (self.head as usize + self.len as usize) % QUEUE_SIZE;
+            self.events[index] = Some(event);
+            self.len += 1;
+        }
+    }
+
+    /// Take the oldest queued event
+    ///
+    /// If `scan()` runs in an interrupt, call this with interrupts disabled.
+    pub fn poll(&mut self) -> Option<KeyEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head as usize].take();
+        self.head = ((self.head as usize + 1) % QUEUE_SIZE) as u8;
+        self.len -= 1;
+        event
+    }
+
+    /// Discard all queued events
+    pub fn clear_events(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Debounced state of all keys
+    pub const fn matrix(&self) -> KeyMatrix {
+        self.stable
+    }
+
+    /// True if `key` is held down
+    pub const fn is_pressed(&self, key: Key) -> bool {
+        self.stable.is_pressed(key)
+    }
+
+    /// Modifier keys held down
+    pub const fn modifiers(&self) -> Modifiers {
+        self.stable.modifiers()
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
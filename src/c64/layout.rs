@@ -0,0 +1,341 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! VIC-II memory layout planner
+//!
+//! The VIC-II sees one 16 KiB bank selected by CIA2, and within it the screen,
+//! charset, bitmap and sprite data at locations set in `VMCSB` (0xd018).
+//! `VicLayout` takes absolute addresses and checks that everything is in the
+//! same bank, correctly aligned, not overlapping, and not in the character ROM
+//! shadow at 0x1000-0x1fff and 0x9000-0x9fff where the VIC-II reads ROM
+//! instead of RAM. When built as a `const` and finished with `validate()`,
+//! violations are compile time errors.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::layout::VicLayout;
+//!
+//! const LAYOUT: VicLayout = VicLayout::new(0x4400)
+//!     .charset(0x4800)
+//!     .sprites(0x5000, 8)
+//!     .validate();
+//! const SPRITE_POINTER: u8 = LAYOUT.sprite_pointer(0x5040);
+//! LAYOUT.apply();
+//! ~~~
+//!
+//! This fails to compile as 0x1000 shows the character ROM:
+//! ~~~compile_fail
+//! use mos_hardware::c64::layout::VicLayout;
+//!
+//! const LAYOUT: VicLayout = VicLayout::new(0x0400).charset(0x1000).validate();
+//! ~~~
+//!
+//! So does this, as the ROM charset is not visible in bank 1:
+//! ~~~compile_fail
+//! use mos_hardware::c64::layout::VicLayout;
+//!
+//! const LAYOUT: VicLayout = VicLayout::new(0x4400).validate();
+//! ~~~
+
+use super::{set_vic_bank, vic2};
+use crate::cia::CIA2PortA;
+use crate::vic2::{CharsetBank, MemorySetup, ScreenBank};
+
+/// Size of a VIC bank
+pub const BANK_SIZE: u16 = 0x4000;
+/// Size of screen memory including the sprite pointers
+pub const SCREEN_SIZE: u16 = 0x0400;
+/// Size of a charset
+pub const CHARSET_SIZE: u16 = 0x0800;
+/// Size of a hires or multicolor bitmap
+pub const BITMAP_SIZE: u16 = 8000;
+/// Size of a sprite including the padding byte
+pub const SPRITE_SIZE: u16 = 64;
+/// Offset of the sprite pointers in screen memory
+pub const SPRITE_POINTERS_OFFSET: u16 = 0x03f8;
+
+/// Start of the character ROM shadow relative to banks 0 and 2
+const CHAR_ROM_SHADOW: u16 = 0x1000;
+/// Size of the character ROM shadow
+const CHAR_ROM_SHADOW_SIZE: u16 = 0x1000;
+
+/// VIC bank (0-3) containing `address`
+const fn bank_of(address: u16) -> u8 {
+    (address / BANK_SIZE) as u8
+}
+
+/// True if `[a, a + a_size)` and `[b, b + b_size)` overlap
+const fn overlaps(a: u16, a_size: u16, b: u16, b_size: u16) -> bool {
+    (a as u32) < b as u32 + b_size as u32 && (b as u32) < a as u32 + a_size as u32
+}
+
+/// True if `[address, address + size)` is inside the character ROM shadow
+const fn in_char_rom_shadow(address: u16, size: u16) -> bool {
+    match bank_of(address) {
+        0 | 2 => {
+            let shadow = (address & 0x8000) + CHAR_ROM_SHADOW;
+            overlaps(address, size, shadow, CHAR_ROM_SHADOW_SIZE)
+        }
+        _ => false,
+    }
+}
+
+/// Charset as seen by the VIC-II
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Charset {
+    /// Default ROM charset, only visible in banks 0 and 2
+    Unset,
+    /// Charset in RAM at an absolute address
+    Ram(u16),
+    /// Character ROM at offset `0` or `0x800`, only visible in banks 0 and 2
+    Rom(u16),
+}
+
+/// Validated memory layout of a VIC bank
+///
+/// All builder methods are `const` and panic if the layout is invalid.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VicLayout {
+    screen: u16,
+    charset: Charset,
+    bitmap: Option<u16>,
+    /// Start address and number of sprites
+    sprites: Option<(u16, u8)>,
+}
+
+impl VicLayout {
+    /// Layout with screen memory at `screen`, which must be 1 KiB aligned
+    ///
+    /// The screen selects the VIC bank that everything else must be in.
+    pub const fn new(screen: u16) -> Self {
+        assert!(
+            screen.is_multiple_of(SCREEN_SIZE),
+            "screen must be 1 KiB aligned"
+        );
+        assert!(
+            !in_char_rom_shadow(screen, SCREEN_SIZE),
+            "screen is in the character ROM shadow"
+        );
+        Self {
+            screen,
+            charset: Charset::Unset,
+            bitmap: None,
+            sprites: None,
+        }
+    }
+
+    /// Check that a region is in the same bank, outside the ROM shadow, and not overlapping others
+    const fn check_region(&self, address: u16, size: u16) {
+        let last = match address.checked_add(size - 1) {
+            Some(last) => last,
+            None => panic!("region extends past the end of memory"),
+        };
+        assert!(
+            bank_of(address) == self.bank() && bank_of(last) == self.bank(),
+            "region must be in the VIC bank of the screen"
+        );
+        assert!(
+            !in_char_rom_shadow(address, size),
+            "region is in the character ROM shadow"
+        );
+        assert!(
+            !overlaps(address, size, self.screen, SCREEN_SIZE),
+            "region overlaps the screen"
+        );
+        if let Charset::Ram(charset) = self.charset {
+            assert!(
+                !overlaps(address, size, charset, CHARSET_SIZE),
+                "region overlaps the charset"
+            );
+        }
+        if let Some(bitmap) = self.bitmap {
+            assert!(
+                !overlaps(address, size, bitmap, BITMAP_SIZE),
+                "region overlaps the bitmap"
+            );
+        }
+        if let Some((sprites, count)) = self.sprites {
+            assert!(
+                !overlaps(address, size, sprites, count as u16 * SPRITE_SIZE),
+                "region overlaps the sprites"
+            );
+        }
+    }
+
+    /// Charset in RAM at `address`, which must be 2 KiB aligned
+    pub const fn charset(self, address: u16) -> Self {
+        assert!(
+            address.is_multiple_of(CHARSET_SIZE),
+            "charset must be 2 KiB aligned"
+        );
+        assert!(self.bitmap.is_none(), "charset and bitmap are exclusive");
+        self.check_region(address, CHARSET_SIZE);
+        Self {
+            charset: Charset::Ram(address),
+            ..self
+        }
+    }
+
+    /// Charset from the character ROM; see `banking::UPPER_CASE_OFFSET` and `banking::MIXED_CASE_OFFSET`
+    pub const fn rom_charset(self, offset: u16) -> Self {
+        assert!(offset == 0 || offset == CHARSET_SIZE, "invalid ROM charset");
+        assert!(
+            self.bank().is_multiple_of(2),
+            "character ROM is only visible in VIC banks 0 and 2"
+        );
+        assert!(self.bitmap.is_none(), "charset and bitmap are exclusive");
+        Self {
+            charset: Charset::Rom(offset),
+            ..self
+        }
+    }
+
+    /// Bitmap at `address`, which must be at the start or middle of the bank
+    pub const fn bitmap(self, address: u16) -> Self {
+        assert!(
+            address.is_multiple_of(0x2000),
+            "bitmap must be 8 KiB aligned"
+        );
+        assert!(
+            matches!(self.charset, Charset::Unset),
+            "charset and bitmap are exclusive"
+        );
+        self.check_region(address, BITMAP_SIZE);
+        Self {
+            bitmap: Some(address),
+            ..self
+        }
+    }
+
+    /// Reserve `count` consecutive sprites starting at `address`, which must be 64 byte aligned
+    pub const fn sprites(self, address: u16, count: u8) -> Self {
+        assert!(
+            address.is_multiple_of(SPRITE_SIZE),
+            "sprites must be 64 byte aligned"
+        );
+        assert!(count > 0, "no sprites");
+        assert!(self.sprites.is_none(), "sprites already placed");
+        self.check_region(address, count as u16 * SPRITE_SIZE);
+        Self {
+            sprites: Some((address, count)),
+            ..self
+        }
+    }
+
+    /// Check the layout as a whole; use as the last builder call of a `const` layout
+    ///
+    /// The ROM charset used when neither a charset nor a bitmap is placed is only
+    /// visible in VIC banks 0 and 2, so banks 1 and 3 need one of them.
+    pub const fn validate(self) -> Self {
+        assert!(
+            self.bank().is_multiple_of(2)
+                || self.bitmap.is_some()
+                || !matches!(self.charset, Charset::Unset),
+            "VIC banks 1 and 3 need a charset or bitmap"
+        );
+        self
+    }
+
+    /// VIC bank (0-3)
+    pub const fn bank(&self) -> u8 {
+        bank_of(self.screen)
+    }
+
+    /// First address of the VIC bank
+    pub const fn bank_address(&self) -> u16 {
+        self.bank() as u16 * BANK_SIZE
+    }
+
+    /// Value for `c64::set_vic_bank()`
+    pub const fn vic_bank(&self) -> CIA2PortA {
+        CIA2PortA::from_bits_truncate(3 - self.bank())
+    }
+
+    /// Value for the memory setup register (0xd018)
+    ///
+    /// Panics if the layout does not pass `validate()`.
+    pub const fn memory_setup(&self) -> MemorySetup {
+        self.validate();
+        let setup =
+            MemorySetup::from_bits(0).screen(ScreenBank::from_address(self.screen % BANK_SIZE));
+        match (self.bitmap, self.charset) {
+            (Some(bitmap), _) => setup.charset(CharsetBank::from(bitmap % BANK_SIZE)),
+            (None, Charset::Ram(charset)) => setup.charset(CharsetBank::from(charset % BANK_SIZE)),
+            (None, Charset::Rom(offset)) => {
+                setup.charset(CharsetBank::from(CHAR_ROM_SHADOW + offset))
+            }
+            (None, Charset::Unset) => setup.charset(CharsetBank::DEFAULT),
+        }
+    }
+
+    /// Absolute address of screen memory
+    pub const fn screen_address(&self) -> u16 {
+        self.screen
+    }
+
+    /// Absolute address of the eight sprite pointers at the end of screen memory
+    pub const fn sprite_pointers_address(&self) -> u16 {
+        self.screen + SPRITE_POINTERS_OFFSET
+    }
+
+    /// Absolute address of the charset in RAM, if any
+    pub const fn charset_address(&self) -> Option<u16> {
+        match self.charset {
+            Charset::Ram(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// Absolute address of the bitmap, if any
+    pub const fn bitmap_address(&self) -> Option<u16> {
+        self.bitmap
+    }
+
+    /// Absolute address of sprite `index` among the reserved sprites
+    pub const fn sprite_address(&self, index: u8) -> u16 {
+        match self.sprites {
+            Some((address, count)) => {
+                assert!(index < count, "sprite index out of range");
+                address + index as u16 * SPRITE_SIZE
+            }
+            None => panic!("no sprites reserved"),
+        }
+    }
+
+    /// Sprite pointer for sprite data at absolute `address` in this bank
+    pub const fn sprite_pointer(&self, address: u16) -> u8 {
+        assert!(
+            address.is_multiple_of(SPRITE_SIZE),
+            "sprites must be 64 byte aligned"
+        );
+        assert!(
+            bank_of(address) == self.bank(),
+            "sprite must be in the VIC bank of the screen"
+        );
+        ((address % BANK_SIZE) / SPRITE_SIZE) as u8
+    }
+
+    /// Select the VIC bank and write the memory setup register
+    pub fn apply(&self) {
+        set_vic_bank(self.vic_bank());
+        unsafe { vic2().screen_and_charset_bank.write(self.memory_setup()) };
+    }
+}
+
+impl Default for VicLayout {
+    /// Layout after reset with screen at 0x0400 and the upper case ROM charset
+    fn default() -> Self {
+        Self::new(0x0400).rom_charset(0)
+    }
+}
@@ -17,15 +17,27 @@
 //! Future information may be incorporated using the
 //! [Ultimate Commodore 64 Reference](https://github.com/mist64/c64ref)
 
+pub mod banking;
 mod cpu6510;
+pub mod datasette;
+pub mod iec;
+pub mod irq;
+pub mod keyboard;
+pub mod layout;
 mod mos6526;
+pub mod multiplexer;
+pub mod raster;
+pub mod rs232;
+pub mod scroll;
+pub mod sprites;
+pub mod text_screen;
 use crate::cia::*;
+use crate::interrupt;
+use crate::peripheral::Peripheral;
 use crate::sid::*;
 use crate::vic2::*;
-use cpu6510::*;
-use mos6526::*;
 
-pub use cpu6510::{CPU_PORT, CPU_PORT_DDR};
+pub use cpu6510::{Cpu6510, CpuPortDdrFlags, CpuPortFlags, CPU_PORT, CPU_PORT_DDR};
 
 /// Pointer to CPU 6510
 pub const CPU: *const Cpu6510 = (0x0000) as _;
@@ -89,19 +101,12 @@ pub const CIA2: *const MOSComplexInterfaceAdapter6526_2 = (0xdd00) as _;
 /// Pointer to the KERNAL ROM memory area (0xe000 - 0xffff)
 pub const KERNAL_ROM: *mut [u8; 8192] = (0xe000) as _;
 
-extern "C" {
-    // defined in c to allow assembly and interrupt attribute
-    fn hardware_raster_irq_c(triggering_raster_line: u8);
-}
-
 /// Setup hardware raster interrupt (0xfffe)
 ///
-/// This registers a Rust function, `called_every_frame()` to be triggered
+/// This registers a function or closure, `handler`, to be triggered
 /// at a specific raster line. The BASIC and KERNAL roms are disabled so
 /// suffix your main program with an endless loop.
-/// `fn called_every_frame()` must be defined and *exported*
-/// on the Rust side and will be called from C via a wrapper. This is because
-/// the LLVM `__interrupt__` attribute is currently not available from Rust.
+/// See the `irq` module for more interrupt sources and for keeping the KERNAL.
 ///
 /// # Examples
 /// ```
@@ -109,21 +114,20 @@ extern "C" {
 /// #![no_main]
 /// ...
 ///
-/// #[no_mangle]
-/// pub unsafe extern fn called_every_frame() {
+/// fn called_every_frame() {
 ///    ...
 /// }
 ///
 /// #[no_mangle]
 /// extern "C" fn main(_argc: core::ffi::c_int, _argv: *const *const u8) -> core::ffi::c_int {
-///    c64::hardware_raster_irq(100); // trigger at raster line 100
-///    loop {}                        // let's not return to dead BASIC
+///    c64::hardware_raster_irq(100, called_every_frame); // trigger at raster line 100
+///    loop {}                                            // let's not return to dead BASIC
 /// }
 /// ```
-pub fn hardware_raster_irq(triggering_raster_line: u8) {
-    unsafe {
-        hardware_raster_irq_c(triggering_raster_line);
-    }
+pub fn hardware_raster_irq<F: FnMut() + 'static>(triggering_raster_line: u8, handler: F) {
+    irq::install(irq::Vectors::Hardware);
+    vic2().set_raster_irq_line(triggering_raster_line as u16);
+    irq::set_handler(irq::Interrupt::Raster, handler);
 }
 
 /// Special keyboard and PETSCII codes
@@ -187,6 +191,58 @@ pub const fn cia2_control() -> &'static Mos6526ControlBlock {
     unsafe { &(*CIA2).control }
 }
 
+/// Set once `Peripherals` have been taken
+static mut PERIPHERALS_TAKEN: bool = false;
+
+/// Owned handles to all C64 chips
+///
+/// # Examples
+/// ~~~
+/// let mut peripherals = c64::Peripherals::take().unwrap();
+/// peripherals.cia1.reset();
+/// peripherals.cia2.reset();
+/// peripherals.sid.start_random_generator();
+/// ~~~
+pub struct Peripherals {
+    /// CPU port (0x0000)
+    pub cpu: Peripheral<Cpu6510, 0x0000>,
+    /// Video interface controller (0xd000)
+    pub vic2: Peripheral<MOSVideoInterfaceControllerII, 0xd000>,
+    /// Sound interface device (0xd400)
+    pub sid: Peripheral<MOSSoundInterfaceDevice, 0xd400>,
+    /// First complex interface adapter (0xdc00)
+    pub cia1: Peripheral<MOSComplexInterfaceAdapter6526_1, 0xdc00>,
+    /// Second complex interface adapter (0xdd00)
+    pub cia2: Peripheral<MOSComplexInterfaceAdapter6526_2, 0xdd00>,
+}
+
+impl Peripherals {
+    /// Take all peripherals; returns `None` if already taken
+    pub fn take() -> Option<Self> {
+        interrupt::free(|| unsafe {
+            match PERIPHERALS_TAKEN {
+                true => None,
+                false => Some(Self::steal()),
+            }
+        })
+    }
+
+    /// Take all peripherals, regardless of whether they have already been taken
+    ///
+    /// # Safety
+    /// Unsafe as this may create multiple handles to the same chip
+    pub unsafe fn steal() -> Self {
+        PERIPHERALS_TAKEN = true;
+        Self {
+            cpu: Peripheral::new(),
+            vic2: Peripheral::new(),
+            sid: Peripheral::new(),
+            cia1: Peripheral::new(),
+            cia2: Peripheral::new(),
+        }
+    }
+}
+
 /// Clears screen, functional style (fill with SPACE character)
 pub fn clear_screen() {
     unsafe {
@@ -199,14 +255,18 @@ pub fn clear_screen() {
 /// Shift to lower case ROM charset
 pub fn set_lower_case() {
     unsafe {
-        vic2().screen_and_charset_bank.write(23);
+        vic2()
+            .screen_and_charset_bank
+            .modify(|r| r.charset(CharsetBank::AT_1800));
     }
 }
 
 /// Shift to upper case ROM charset
 pub fn set_upper_case() {
     unsafe {
-        vic2().screen_and_charset_bank.write(21);
+        vic2()
+            .screen_and_charset_bank
+            .modify(|r| r.charset(CharsetBank::AT_1000));
     }
 }
 
@@ -225,22 +285,18 @@ pub fn set_upper_case() {
 /// set_vic_bank(CIA2PortA::VIC_BANK_1);
 /// ```
 pub fn set_vic_bank(bank: CIA2PortA) {
-    // Secure argument input
-    let bank = u8::from(bank) & 0b11;
-
-    let mut port_a = cia2().port_a.read();
-
-    unsafe {
-        // Configure for VIC bank control
-        cia2()
-            .data_direction_port_a
-            .modify(|dir_a| dir_a | CIA2DirectionA::VA15 | CIA2DirectionA::VA14);
-    }
+    cia2().set_vic_bank(bank);
+}
 
-    // Set the VIC bank using the provided constant
-    port_a.set_vic_bank(bank);
+/// First address of the active VIC bank selected by CIA2
+pub fn get_vic_bank_address() -> u16 {
+    (3 - cia2().port_a.read().get_vic_bank()) as u16 * 0x4000
+}
 
-    unsafe {
-        cia2().port_a.write(port_a);
-    }
+/// Absolute address of the active screen memory, from the VIC bank and `VMCSB`
+///
+/// The sprite shape pointers are the last eight bytes of the 1 KiB screen memory.
+pub fn get_screen_address() -> u16 {
+    let screen = vic2().screen_and_charset_bank.read().get_screen();
+    get_vic_bank_address() + ((screen.bits() as u16) << 6)
 }
@@ -1,16 +1,5 @@
-use core::mem::size_of;
-use static_assertions::const_assert;
-
 use crate::cia::*;
 
-pub type MOSComplexInterfaceAdapter6526_1 =
-    MOSComplexInterfaceAdapter6526<CIA1PortA, CIA1PortB, CIA1DirectionA, CIA1DirectionB>;
-pub type MOSComplexInterfaceAdapter6526_2 =
-    MOSComplexInterfaceAdapter6526<CIA2PortA, CIA2PortB, CIA2DirectionA, CIA2DirectionB>;
-
-const_assert!(size_of::<MOSComplexInterfaceAdapter6526_1>() == 16);
-const_assert!(size_of::<MOSComplexInterfaceAdapter6526_2>() == 16);
-
 impl MOSComplexInterfaceAdapter6526_1 {
     /// reset CIA#1, prepare keyboard with STOP key
     pub fn reset(&mut self) {
@@ -36,10 +25,10 @@ impl MOSComplexInterfaceAdapter6526_1 {
     /// Enable IRQ and timer 1. Keep TOD
     pub fn enable_keyboard(&mut self) {
         unsafe {
-            // Enable IRQ
+            // Enable timer A interrupt
             self.control
                 .interrupt
-                .write(InterruptControl::SET_CLEAR | InterruptControl::IRQ);
+                .write(InterruptControl::SET_CLEAR | InterruptControl::TIMER_A);
 
             // save only tod bit and enable timer 1
             self.control
@@ -72,6 +61,27 @@ impl MOSComplexInterfaceAdapter6526_2 {
         }
     }
 
+    /// Select one of four memory ranges that VIC II sees (see `c64::set_vic_bank()`)
+    pub fn set_vic_bank(&self, bank: CIA2PortA) {
+        // Secure argument input
+        let bank = u8::from(bank) & 0b11;
+
+        let mut port_a = self.port_a.read();
+
+        unsafe {
+            // Configure for VIC bank control
+            self.data_direction_port_a
+                .modify(|dir_a| dir_a | CIA2DirectionA::VA15 | CIA2DirectionA::VA14);
+        }
+
+        // Set the VIC bank using the provided constant
+        port_a.set_vic_bank(bank);
+
+        unsafe {
+            self.port_a.write(port_a);
+        }
+    }
+
     /// set clock line low  (inverted)
     pub fn clkhi(&mut self) {
         unsafe {
@@ -0,0 +1,300 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Raster interrupt driven sprite multiplexer
+//!
+//! Shows up to `MAX_SPRITES` virtual sprites with the eight hardware sprites by
+//! reusing a hardware sprite further down the screen once it has been drawn.
+//! `update()` sorts the virtual sprites by Y and plans which hardware sprite
+//! shows each of them and at which raster line it is reprogrammed. Sprites that
+//! would start before a hardware sprite is free are dropped. Plans are double
+//! buffered and swapped at the top of the next frame, so `update()` can be
+//! called at any time from the main program.
+//!
+//! The multiplexer takes over the `irq::Interrupt::Raster` handler and therefore
+//! cannot be combined with `c64::raster` chains; `irq::install()` must be called first.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::{irq, multiplexer::{self, VirtualSprite}};
+//!
+//! irq::install(irq::Vectors::Hardware);
+//! multiplexer::start();
+//! let mut sprites = [VirtualSprite::default(); 16];
+//! for (i, sprite) in sprites.iter_mut().enumerate() {
+//!     *sprite = VirtualSprite::new(24 + 20 * i as u16, 50 + 12 * i as u8, 0x80, 1);
+//! }
+//! let dropped = multiplexer::update(&sprites);
+//! ~~~
+
+use super::irq::{self, Interrupt};
+use super::layout::SPRITE_POINTERS_OFFSET;
+use super::{get_screen_address, vic2};
+use crate::interrupt;
+use crate::vic2::Sprites;
+use core::ptr::{addr_of, addr_of_mut};
+
+/// Maximum number of virtual sprites
+pub const MAX_SPRITES: usize = 32;
+/// Height of an unexpanded sprite in raster lines
+pub const SPRITE_HEIGHT: u16 = 21;
+/// Raster line where a new frame starts and plans are swapped
+const TOP_LINE: u16 = 0;
+/// Raster lines before a sprite starts at which its hardware sprite is reprogrammed
+const LEAD_LINES: u16 = 6;
+/// Minimum raster lines between the end of a sprite and a reuse of its hardware sprite
+const MIN_GAP: u16 = 3;
+
+/// Sprite to be shown by the multiplexer
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct VirtualSprite {
+    /// X position (0-511)
+    pub x: u16,
+    /// Y position, equal to the raster line of the first sprite line
+    pub y: u8,
+    /// Shape pointer relative to the VIC bank
+    pub pointer: u8,
+    /// Sprite color
+    pub color: u8,
+}
+
+impl VirtualSprite {
+    pub const fn new(x: u16, y: u8, pointer: u8, color: u8) -> Self {
+        Self {
+            x,
+            y,
+            pointer,
+            color,
+        }
+    }
+}
+
+/// Virtual sprite assigned to a hardware sprite
+#[derive(Copy, Clone, Default)]
+struct Entry {
+    sprite: VirtualSprite,
+    /// Hardware sprite (0-7)
+    slot: u8,
+    /// Raster line at which the hardware sprite is reprogrammed
+    line: u16,
+}
+
+/// Entries of a frame ordered by raster line
+struct Plan {
+    entries: [Entry; MAX_SPRITES],
+    count: u8,
+    /// Virtual sprites that could not be shown
+    dropped: u8,
+    /// Hardware sprites in use
+    enabled: Sprites,
+}
+
+impl Plan {
+    const EMPTY: Self = Self {
+        entries: [Entry {
+            sprite: VirtualSprite::new(0, 0, 0, 0),
+            slot: 0,
+            line: 0,
+        }; MAX_SPRITES],
+        count: 0,
+        dropped: 0,
+        enabled: Sprites::empty(),
+    };
+
+    /// Sort `sprites` by Y and assign each to the hardware sprite that is free first
+    fn build(&mut self, sprites: &[VirtualSprite]) {
+        let mut order = [0u8; MAX_SPRITES];
+        for i in 0..sprites.len() {
+            order[i] = i as u8;
+            let mut j = i;
+            while j > 0 && sprites[order[j - 1] as usize].y > sprites[order[j] as usize].y {
+                order.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        // raster line after the last sprite shown by each hardware sprite
+        let mut ends: [Option<u16>; 8] = [None; 8];
+        self.count = 0;
+        self.dropped = 0;
+        self.enabled = Sprites::empty();
+        for index in &order[..sprites.len()] {
+            let sprite = sprites[*index as usize];
+            let y = sprite.y as u16;
+            let slot = (0..8).min_by_key(|slot| ends[*slot]).unwrap_or(0);
+            let line = match ends[slot] {
+                None => TOP_LINE,
+                Some(end) if y >= end + MIN_GAP => end.max(y.saturating_sub(LEAD_LINES)),
+                Some(_) => {
+                    self.dropped += 1;
+                    continue;
+                }
+            };
+            ends[slot] = Some(y + SPRITE_HEIGHT);
+            self.enabled |= Sprites::new(slot as u8);
+            self.entries[self.count as usize] = Entry {
+                sprite,
+                slot: slot as u8,
+                line,
+            };
+            self.count += 1;
+        }
+    }
+}
+
+/// Plans and raster interrupt progress
+struct State {
+    plans: [Plan; 2],
+    /// Index of the plan being shown
+    front: u8,
+    /// True if the back plan is ready to be shown; shared with the main program
+    pending: bool,
+    /// Index of the next entry to show
+    next: u8,
+    /// True if a sprite was reprogrammed after the beam reached it in the current frame
+    late: bool,
+    dropped_frames: u16,
+    /// Address of the sprite pointers
+    pointers: u16,
+}
+
+impl State {
+    fn is_pending(&self) -> bool {
+        unsafe { addr_of!(self.pending).read_volatile() }
+    }
+
+    fn set_pending(&mut self, pending: bool) {
+        unsafe { addr_of_mut!(self.pending).write_volatile(pending) };
+    }
+
+    /// Start a frame: count drops in the previous one and swap in a pending plan
+    fn begin_frame(&mut self) {
+        let shown = &self.plans[self.front as usize];
+        if shown.dropped > 0 || self.late {
+            self.dropped_frames = self.dropped_frames.wrapping_add(1);
+        }
+        self.late = false;
+        if self.is_pending() {
+            self.front ^= 1;
+            self.set_pending(false);
+        }
+        unsafe {
+            vic2()
+                .sprite_enable
+                .write(self.plans[self.front as usize].enabled)
+        };
+    }
+
+    /// Program hardware sprite of `entry`
+    fn show(&mut self, entry: &Entry, raster_line: u16) {
+        let sprite = &entry.sprite;
+        if (sprite.y as u16) <= raster_line && entry.line != TOP_LINE {
+            self.late = true;
+        }
+        let vic = vic2();
+        vic.set_sprite_position(entry.slot, sprite.x, sprite.y);
+        vic.set_sprite_color(entry.slot, sprite.color);
+        unsafe {
+            ((self.pointers + entry.slot as u16) as *mut u8).write_volatile(sprite.pointer);
+        }
+    }
+}
+
+static mut STATE: State = State {
+    plans: [Plan::EMPTY, Plan::EMPTY],
+    front: 0,
+    pending: false,
+    next: 0,
+    late: false,
+    dropped_frames: 0,
+    pointers: 0x0400 + SPRITE_POINTERS_OFFSET,
+};
+
+/// Raster interrupt handler showing the sprites of the current entry and those already due
+fn on_raster() {
+    let state = unsafe { &mut *addr_of_mut!(STATE) };
+    if state.next == 0 {
+        state.begin_frame();
+    }
+    let front = state.front as usize;
+    let count = state.plans[front].count;
+    let mut raster_line = vic2().get_raster_line();
+    let mut first = true;
+    while state.next < count {
+        let entry = state.plans[front].entries[state.next as usize];
+        if !first && entry.line > raster_line {
+            break;
+        }
+        state.show(&entry, raster_line);
+        state.next += 1;
+        first = false;
+        raster_line = vic2().get_raster_line();
+    }
+    if state.next < count {
+        vic2().set_raster_irq_line(state.plans[front].entries[state.next as usize].line);
+    } else {
+        state.next = 0;
+        vic2().set_raster_irq_line(TOP_LINE);
+    }
+}
+
+/// Start multiplexing with sprite pointers in the active screen memory
+///
+/// Registers the raster interrupt handler.
+pub fn start() {
+    set_screen(get_screen_address());
+    interrupt::free(|| unsafe {
+        (*addr_of_mut!(STATE)).next = 0;
+        vic2().set_raster_irq_line(TOP_LINE);
+    });
+    irq::set_handler(Interrupt::Raster, on_raster);
+}
+
+/// Stop multiplexing and hide all hardware sprites
+pub fn stop() {
+    irq::remove_handler(Interrupt::Raster);
+    unsafe { vic2().sprite_enable.write(Sprites::empty()) };
+}
+
+/// Write sprite pointers to screen memory at `screen`, _e.g._ after switching screens
+pub fn set_screen(screen: u16) {
+    interrupt::free(|| unsafe {
+        (*addr_of_mut!(STATE)).pointers = screen + SPRITE_POINTERS_OFFSET;
+    });
+}
+
+/// Show `sprites` from the next frame on; returns the number of sprites that must be dropped
+///
+/// At most eight sprites can share a raster line, and a hardware sprite can
+/// only be reused a few lines after its previous sprite ends.
+pub fn update(sprites: &[VirtualSprite]) -> u8 {
+    assert!(sprites.len() <= MAX_SPRITES);
+    let state = unsafe { &mut *addr_of_mut!(STATE) };
+    // keep the interrupt from swapping plans while the back plan is written
+    interrupt::free(|| state.set_pending(false));
+    let back = &mut state.plans[(state.front ^ 1) as usize];
+    back.build(sprites);
+    let dropped = back.dropped;
+    state.set_pending(true);
+    dropped
+}
+
+/// Number of frames in which sprites were dropped or reprogrammed too late
+pub fn dropped_frames() -> u16 {
+    interrupt::free(|| unsafe { (*addr_of!(STATE)).dropped_frames })
+}
+
+/// Reset the count of dropped frames
+pub fn reset_dropped_frames() {
+    interrupt::free(|| unsafe { (*addr_of_mut!(STATE)).dropped_frames = 0 });
+}
@@ -0,0 +1,220 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Raster interrupt chains for split screen effects
+//!
+//! A chain is an ordered list of raster lines (0-311 on PAL, 0-262 on NTSC), each with
+//! a handler. Lines are checked against the raster lines of the running machine, which
+//! is detected with `detect::c64()` when the first split is added. On every raster
+//! interrupt the handler of the current split is called and the VIC-II is reprogrammed
+//! to trigger at the next line, including bit 8 in `ControlYFlags::RASTER_COMPARE`. If
+//! a handler runs past the line of the following split, that split is called right
+//! away instead of being delayed by a frame.
+//!
+//! The chain is driven by the `irq::Interrupt::Raster` handler so `irq::install()`
+//! must be called first.
+//!
+//! # Examples
+//!
+//! A static table of splits:
+//! ~~~
+//! use mos_hardware::{c64, vic2};
+//! use mos_hardware::c64::{irq, raster};
+//!
+//! fn top() { unsafe { c64::vic2().background_color0.write(vic2::BLUE) } }
+//! fn bottom() { unsafe { c64::vic2().background_color0.write(vic2::BLACK) } }
+//!
+//! irq::install(irq::Vectors::Hardware);
+//! raster::set_table(&[(50, top), (250, bottom)]);
+//! raster::start();
+//! ~~~
+//!
+//! Splits can be added and removed at runtime:
+//! ~~~
+//! let mut color = 0;
+//! raster::add_split(150, move || {
+//!     color = (color + 1) & 0x0f;
+//!     unsafe { c64::vic2().border_color.write(color) };
+//! });
+//! // ...
+//! raster::remove_split(150);
+//! ~~~
+
+use super::irq::{self, Interrupt};
+use super::vic2;
+use crate::detect::{self, VideoStandard};
+use crate::interrupt;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ptr::addr_of_mut;
+
+/// Number of raster lines on a PAL machine
+pub const PAL_RASTER_LINES: u16 = VideoStandard::Pal.raster_lines();
+
+/// Number of raster lines on an NTSC machine
+pub const NTSC_RASTER_LINES: u16 = VideoStandard::Ntsc.raster_lines();
+
+/// Raster lines of the running machine; zero until detected
+static mut RASTER_LINES: u16 = 0;
+
+/// Number of raster lines of the running machine
+///
+/// Detected with `detect::c64()` on the first call, which disables interrupts
+/// for up to three frames.
+pub fn raster_lines() -> u16 {
+    unsafe {
+        if RASTER_LINES == 0 {
+            RASTER_LINES = detect::c64().standard.raster_lines();
+        }
+        RASTER_LINES
+    }
+}
+
+/// Handler called at a given raster line
+struct Split {
+    line: u16,
+    handler: Box<dyn FnMut()>,
+}
+
+/// Splits sorted by raster line and index of the split the VIC-II waits for
+struct Chain {
+    splits: Vec<Split>,
+    next: usize,
+}
+
+impl Chain {
+    /// Wait for the first split below the current beam position, or the first in the next frame
+    fn reschedule(&mut self) {
+        let line = vic2().get_raster_line();
+        self.next = self
+            .splits
+            .iter()
+            .position(|split| split.line > line)
+            .unwrap_or(0);
+        if let Some(split) = self.splits.get(self.next) {
+            vic2().set_raster_irq_line(split.line);
+        }
+    }
+}
+
+static mut CHAIN: Chain = Chain {
+    splits: Vec::new(),
+    next: 0,
+};
+
+/// Modify the chain with interrupts disabled and reprogram the raster line
+fn modify_chain<F: FnOnce(&mut Chain) -> R, R>(f: F) -> R {
+    interrupt::free(|| {
+        let chain = unsafe { &mut *addr_of_mut!(CHAIN) };
+        let result = f(chain);
+        chain.reschedule();
+        result
+    })
+}
+
+/// Raster interrupt handler driving the chain
+fn on_raster() {
+    let chain = unsafe { &mut *addr_of_mut!(CHAIN) };
+    while let Some(split) = chain.splits.get_mut(chain.next) {
+        (split.handler)();
+        chain.next = (chain.next + 1) % chain.splits.len();
+        let line = chain.splits[chain.next].line;
+        if chain.next == 0 || line > vic2().get_raster_line() {
+            vic2().set_raster_irq_line(line);
+            return;
+        }
+    }
+}
+
+/// Start calling the splits by registering the raster interrupt handler
+pub fn start() {
+    modify_chain(|_| ());
+    irq::set_handler(Interrupt::Raster, on_raster);
+}
+
+/// Stop the chain by removing the raster interrupt handler; splits are kept
+pub fn stop() {
+    irq::remove_handler(Interrupt::Raster);
+}
+
+/// Call `handler` at raster `line` in every frame
+///
+/// The split is inserted in order and replaces any existing split at the same line.
+/// Must not be called from within a split handler.
+///
+/// # Panics
+/// If `line` is not below `raster_lines()`, _e.g._ 262 or more on NTSC.
+pub fn add_split<F: FnMut() + 'static>(line: u16, handler: F) {
+    assert!(line < raster_lines());
+    let split = Split {
+        line,
+        handler: Box::new(handler),
+    };
+    modify_chain(
+        |chain| match chain.splits.binary_search_by_key(&line, |s| s.line) {
+            Ok(index) => chain.splits[index] = split,
+            Err(index) => chain.splits.insert(index, split),
+        },
+    );
+}
+
+/// Remove the split at raster `line`; returns `false` if there is none
+///
+/// Must not be called from within a split handler.
+pub fn remove_split(line: u16) -> bool {
+    modify_chain(
+        |chain| match chain.splits.binary_search_by_key(&line, |s| s.line) {
+            Ok(index) => {
+                chain.splits.remove(index);
+                true
+            }
+            Err(_) => false,
+        },
+    )
+}
+
+/// Replace all splits with `table` of `(raster_line, handler)` entries in ascending order
+///
+/// # Panics
+/// If the lines are not ascending or not below `raster_lines()`.
+pub fn set_table(table: &[(u16, fn())]) {
+    assert!(table.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    let lines = raster_lines();
+    assert!(table.iter().all(|(line, _)| *line < lines));
+    modify_chain(|chain| {
+        chain.splits = table
+            .iter()
+            .map(|(line, handler)| Split {
+                line: *line,
+                handler: Box::new(*handler),
+            })
+            .collect();
+    });
+}
+
+/// Remove all splits
+pub fn clear() {
+    modify_chain(|chain| chain.splits.clear());
+}
+
+/// Raster lines of all splits in ascending order
+pub fn lines() -> Vec<u16> {
+    interrupt::free(|| unsafe {
+        (*addr_of_mut!(CHAIN))
+            .splits
+            .iter()
+            .map(|split| split.line)
+            .collect()
+    })
+}
@@ -0,0 +1,415 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Software RS-232 UART on the user port
+//!
+//! Like the KERNAL, the UART is bit-banged by NMIs from CIA2 but runs entirely
+//! in Rust with interrupt-driven ring buffers. The frame format is 8N1:
+//!
+//! - Transmit: timer A paces the bits written to `TXD` (port A bit 2, pin M).
+//! - Receive: the falling edge of the start bit on `FLAG` (pin B, wired to `RXD`
+//!   on pin C) starts timer B, which samples port B bit 0 in the middle of each bit.
+//! - Optional RTS/CTS hardware flow control on port B bits 1 and 6. As with
+//!   the KERNAL, a set bit means asserted, which matches common user port adapters.
+//!
+//! Requires `irq::install()`, which routes the CIA2 NMIs to the handlers. As the
+//! handlers modify CIA2 port A, other code changing port A, _e.g._ the VIC bank,
+//! should do so with the UART idle.
+//!
+//! # Examples
+//! ~~~
+//! use genio::{Read, Write};
+//! use mos_hardware::c64::rs232::{Baud, Config, Rs232};
+//!
+//! irq::install(irq::Vectors::Kernal);
+//! let mut serial = Rs232::open(Config::new(Baud::B2400).with_flow_control(true)).unwrap();
+//! serial.write_all(b"HELLO\r\n").unwrap();
+//! let mut buffer = [0u8; 16];
+//! let received = serial.read(&mut buffer).unwrap();
+//! ~~~
+
+use super::irq::{self, Interrupt};
+use super::{cia2, cia2_control};
+use crate::cia::{
+    CIA2DirectionB, CIA2PortA, CIA2PortB, InterruptControl, RS232Access, Timer, TimerMode,
+    PAL_CLOCK_HZ,
+};
+use crate::interrupt;
+use crate::ring_buffer::RingBuffer;
+use core::convert::Infallible;
+use core::ptr::addr_of_mut;
+
+/// Size of the transmit and receive buffers
+const BUFFER_SIZE: usize = 128;
+
+/// RTS is deasserted when fewer than this number of bytes fit in the receive buffer
+const RTS_THRESHOLD: usize = 16;
+
+/// Supported bit rates
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Baud {
+    B300 = 300,
+    B600 = 600,
+    B1200 = 1200,
+    B2400 = 2400,
+}
+
+/// UART settings
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// Bit rate
+    pub baud: Baud,
+    /// System clock in Hz driving the CIA timers
+    pub clock_hz: u32,
+    /// Use RTS/CTS hardware flow control
+    pub flow_control: bool,
+}
+
+impl Config {
+    /// 8N1 at `baud` on a PAL machine without flow control
+    pub const fn new(baud: Baud) -> Self {
+        Self {
+            baud,
+            clock_hz: PAL_CLOCK_HZ,
+            flow_control: false,
+        }
+    }
+
+    /// Set system clock in Hz, _e.g._ `cia::NTSC_CLOCK_HZ` or `detect::c64().clock_hz`
+    pub const fn with_clock(mut self, clock_hz: u32) -> Self {
+        self.clock_hz = clock_hz;
+        self
+    }
+
+    /// Enable or disable RTS/CTS flow control
+    pub const fn with_flow_control(mut self, enabled: bool) -> Self {
+        self.flow_control = enabled;
+        self
+    }
+
+    /// Cycles per bit
+    const fn bit_cycles(&self) -> u16 {
+        (self.clock_hz / self.baud as u32) as u16
+    }
+}
+
+/// Errors when opening the UART
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Rs232Error {
+    /// The UART is already open
+    AlreadyOpen,
+}
+
+/// UART state shared with the NMI handlers
+struct State {
+    config: Config,
+    transmit: RingBuffer<BUFFER_SIZE>,
+    receive: RingBuffer<BUFFER_SIZE>,
+    /// Remaining bits of the frame being sent, LSB first
+    tx_frame: u16,
+    /// Number of bits left in `tx_frame`
+    tx_bits: u8,
+    /// True while timer A is running
+    tx_active: bool,
+    /// True from the start bit until the stop bit has been sampled
+    rx_active: bool,
+    /// Number of data bits sampled
+    rx_bits: u8,
+    rx_shift: u8,
+    overruns: u16,
+    framing_errors: u16,
+}
+
+static mut STATE: State = State {
+    config: Config::new(Baud::B300),
+    transmit: RingBuffer::new(),
+    receive: RingBuffer::new(),
+    tx_frame: 0,
+    tx_bits: 0,
+    tx_active: false,
+    rx_active: false,
+    rx_bits: 0,
+    rx_shift: 0,
+    overruns: 0,
+    framing_errors: 0,
+};
+
+static mut OPEN: bool = false;
+
+fn state() -> &'static mut State {
+    unsafe { &mut *addr_of_mut!(STATE) }
+}
+
+fn set_txd(mark: bool) {
+    unsafe {
+        cia2().port_a.modify(|mut v| {
+            v.set(CIA2PortA::TXD_OUT, mark);
+            v
+        });
+    }
+}
+
+fn set_rts(asserted: bool) {
+    unsafe {
+        cia2().port_b.modify(|v| {
+            let mut pins = RS232Access::from(v);
+            pins.set(RS232Access::RTS, asserted);
+            CIA2PortB::from(pins)
+        });
+    }
+}
+
+fn is_clear_to_send(state: &State) -> bool {
+    !state.config.flow_control || RS232Access::from(cia2().port_b.read()).contains(RS232Access::CTS)
+}
+
+fn set_flag_interrupt(enabled: bool) {
+    let flags = match enabled {
+        true => InterruptControl::SET_CLEAR | InterruptControl::FLAG,
+        false => InterruptControl::FLAG,
+    };
+    unsafe { cia2_control().interrupt.write(flags) };
+}
+
+/// Load the next byte into the transmit frame and send its start bit; `false` if none
+fn next_frame(state: &mut State) -> bool {
+    if !is_clear_to_send(state) {
+        return false;
+    }
+    match state.transmit.pop() {
+        Some(byte) => {
+            // start bit (0), eight data bits, stop bit (1)
+            state.tx_frame = ((byte as u16) << 1) | 0x200;
+            set_txd(false);
+            state.tx_frame >>= 1;
+            state.tx_bits = 9;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Start the transmitter if idle and bytes are queued
+fn start_transmit() {
+    interrupt::free(|| {
+        let state = state();
+        if !state.tx_active && next_frame(state) {
+            state.tx_active = true;
+            let bit_cycles = state.config.bit_cycles();
+            cia2_control().start_timer(Timer::A, bit_cycles - 1, TimerMode::Continuous);
+        }
+    });
+}
+
+/// Timer A NMI: send the next bit
+fn on_transmit() {
+    let state = state();
+    if !state.tx_active {
+        return;
+    }
+    if state.tx_bits > 0 {
+        set_txd(state.tx_frame & 1 != 0);
+        state.tx_frame >>= 1;
+        state.tx_bits -= 1;
+    } else if !next_frame(state) {
+        cia2_control().stop_timer(Timer::A);
+        state.tx_active = false;
+    }
+}
+
+/// FLAG NMI: falling edge of a start bit
+fn on_start_bit() {
+    let state = state();
+    if state.rx_active {
+        return;
+    }
+    state.rx_active = true;
+    state.rx_bits = 0;
+    set_flag_interrupt(false);
+    // first sample in the middle of data bit 0
+    let bit_cycles = state.config.bit_cycles();
+    cia2_control().start_timer(Timer::B, bit_cycles + bit_cycles / 2, TimerMode::OneShot);
+}
+
+/// Timer B NMI: sample a data or stop bit
+fn on_receive_bit() {
+    let state = state();
+    if !state.rx_active {
+        return;
+    }
+    let mark = RS232Access::from(cia2().port_b.read()).contains(RS232Access::RXD);
+    if state.rx_bits == 0 {
+        let bit_cycles = state.config.bit_cycles();
+        cia2_control().start_timer(Timer::B, bit_cycles - 1, TimerMode::Continuous);
+    }
+    if state.rx_bits < 8 {
+        state.rx_shift = (state.rx_shift >> 1) | ((mark as u8) << 7);
+        state.rx_bits += 1;
+        return;
+    }
+    cia2_control().stop_timer(Timer::B);
+    if !mark {
+        state.framing_errors = state.framing_errors.wrapping_add(1);
+    } else if !state.receive.push(state.rx_shift) {
+        state.overruns = state.overruns.wrapping_add(1);
+    }
+    if state.config.flow_control && state.receive.free() < RTS_THRESHOLD {
+        set_rts(false);
+    }
+    state.rx_active = false;
+    set_flag_interrupt(true);
+}
+
+/// Open UART on the user port
+///
+/// The UART is a singleton, so dropping the returned handle closes it and
+/// stops the NMIs.
+pub struct Rs232 {
+    _private: (),
+}
+
+impl Rs232 {
+    /// Configure CIA2 and register the NMI handlers; requires `irq::install()`
+    pub fn open(config: Config) -> Result<Self, Rs232Error> {
+        interrupt::free(|| {
+            if unsafe { OPEN } {
+                return Err(Rs232Error::AlreadyOpen);
+            }
+            unsafe { OPEN = true };
+            let state = state();
+            state.config = config;
+            state.transmit.clear();
+            state.receive.clear();
+            state.tx_active = false;
+            state.rx_active = false;
+            state.overruns = 0;
+            state.framing_errors = 0;
+            unsafe {
+                cia2()
+                    .data_direction_port_b
+                    .write(CIA2DirectionB::default_as_rs232());
+                cia2().port_b.write(CIA2PortB::from(RS232Access::DTR));
+            }
+            set_txd(true);
+            set_rts(true);
+            irq::set_handler(Interrupt::Cia2TimerA, on_transmit);
+            irq::set_handler(Interrupt::Cia2TimerB, on_receive_bit);
+            irq::set_handler(Interrupt::Cia2Flag, on_start_bit);
+            Ok(Self { _private: () })
+        })
+    }
+
+    /// Take a received byte, if any
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        let state = state();
+        let byte = state.receive.pop();
+        if state.config.flow_control && state.receive.free() >= RTS_THRESHOLD {
+            interrupt::free(|| set_rts(true));
+        }
+        byte
+    }
+
+    /// Queue `byte` for sending; returns `false` if the transmit buffer is full
+    pub fn try_write_byte(&mut self, byte: u8) -> bool {
+        let queued = state().transmit.push(byte);
+        start_transmit();
+        queued
+    }
+
+    /// Number of received bytes waiting to be read
+    pub fn available(&self) -> usize {
+        state().receive.len()
+    }
+
+    /// Number of received bytes dropped because the receive buffer was full
+    pub fn overruns(&self) -> u16 {
+        state().overruns
+    }
+
+    /// Number of received bytes dropped because of a missing stop bit
+    pub fn framing_errors(&self) -> u16 {
+        state().framing_errors
+    }
+}
+
+impl Drop for Rs232 {
+    fn drop(&mut self) {
+        irq::remove_handler(Interrupt::Cia2TimerA);
+        irq::remove_handler(Interrupt::Cia2TimerB);
+        irq::remove_handler(Interrupt::Cia2Flag);
+        interrupt::free(|| {
+            cia2_control().stop_timer(Timer::A);
+            cia2_control().stop_timer(Timer::B);
+            set_txd(true);
+            set_rts(false);
+            unsafe { OPEN = false };
+        });
+    }
+}
+
+impl genio::Read for Rs232 {
+    type ReadError = Infallible;
+
+    /// Wait for at least one byte and read as many as are available
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::ReadError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = loop {
+            if let Some(byte) = self.try_read_byte() {
+                break byte;
+            }
+        };
+        let mut count = 1;
+        for byte in buf[1..].iter_mut() {
+            match self.try_read_byte() {
+                Some(value) => *byte = value,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl genio::Write for Rs232 {
+    type WriteError = Infallible;
+    type FlushError = Infallible;
+
+    /// Wait for space in the transmit buffer and queue as many bytes as fit
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::WriteError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let transmit = &mut state().transmit;
+        while transmit.is_full() {
+            start_transmit();
+        }
+        let count = buf.iter().take_while(|byte| transmit.push(**byte)).count();
+        start_transmit();
+        Ok(count)
+    }
+
+    /// Wait until all queued bytes have been sent
+    fn flush(&mut self) -> Result<(), Self::FlushError> {
+        let state = state();
+        while !state.transmit.is_empty() || unsafe { addr_of_mut!(state.tx_active).read_volatile() }
+        {
+            start_transmit();
+        }
+        Ok(())
+    }
+
+    fn size_hint(&mut self, _bytes: usize) {}
+}
@@ -0,0 +1,391 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Smooth scrolling of a tile map in eight directions
+//!
+//! A `TileMap` of character cells, larger than the screen, is scrolled pixel by
+//! pixel with `XSCROLL` and `YSCROLL` in the 38×24 column and row mode. Whenever
+//! the scroll position crosses a character cell, the next screen has already
+//! been drawn in the back screen so the screens are swapped, and color RAM,
+//! which cannot be double buffered, is shifted in two halves behind the beam:
+//!
+//! | Raster line   | Work                                                    |
+//! |---------------|---------------------------------------------------------|
+//! | `MIDDLE_LINE` | advance the position; shift color RAM of the upper half |
+//! | `BOTTOM_LINE` | set fine scroll, swap screens; shift the lower half     |
+//!
+//! The back screen is drawn by `prepare()`, either from the main program or
+//! after `on_bottom()` in the raster interrupt. If it is not ready in time, the
+//! scroll position waits at the edge of the current cell.
+//!
+//! Both screens are overwritten while scrolling. As llvm-mos programs load at
+//! 0x0801, the default screen at 0x0400 is followed by program code, so the
+//! example below uses the top of VIC bank 0 at 0x3800-0x3fff. That memory must be
+//! kept free of code and data, _e.g._ by keeping the program below 0x3800 or
+//! reserving the range in the linker script.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::{irq, raster, scroll::{self, Direction, ScrollEngine, TileMap}};
+//!
+//! static CELLS: [u8; 80 * 50] = [0x51; 80 * 50];
+//! static COLORS: [u8; 80 * 50] = [vic2::GREEN; 80 * 50];
+//! static mut ENGINE: Option<ScrollEngine> = None;
+//!
+//! fn middle() { unsafe { ENGINE.as_mut().unwrap().on_middle() } }
+//! fn bottom() {
+//!     let engine = unsafe { ENGINE.as_mut().unwrap() };
+//!     engine.on_bottom();
+//!     engine.prepare();
+//! }
+//!
+//! let map = TileMap::new(80, 50, &CELLS, &COLORS);
+//! let screens = [vic2::ScreenBank::AT_3800, vic2::ScreenBank::AT_3C00];
+//! let mut engine = ScrollEngine::new(map, screens);
+//! engine.start();
+//! engine.scroll(Direction::DownRight, 2);
+//! unsafe { ENGINE = Some(engine) };
+//! irq::install(irq::Vectors::Hardware);
+//! raster::set_table(&[(scroll::MIDDLE_LINE, middle), (scroll::BOTTOM_LINE, bottom)]);
+//! raster::start();
+//! ~~~
+
+use super::{get_vic_bank_address, vic2, COLOR_RAM};
+use crate::interrupt;
+use crate::vic2::{ControlXFlags, ControlYFlags, ScreenBank};
+use core::ops::Range;
+use core::ptr::copy_nonoverlapping;
+
+/// Number of columns in screen memory
+pub const COLUMNS: u16 = 40;
+/// Number of rows in screen memory
+pub const ROWS: u16 = 25;
+/// Raster line below the upper half of the screen, see `ScrollEngine::on_middle()`
+pub const MIDDLE_LINE: u16 = 160;
+/// Raster line below the visible screen, see `ScrollEngine::on_bottom()`
+pub const BOTTOM_LINE: u16 = 250;
+/// First row of the lower half of the screen
+const SPLIT_ROW: u16 = 12;
+/// Largest scroll speed in pixels per frame
+pub const MAX_SPEED: u8 = 7;
+
+/// Map of character cells with a screen code and a color for each cell
+#[derive(Copy, Clone)]
+pub struct TileMap<'a> {
+    columns: u16,
+    rows: u16,
+    cells: &'a [u8],
+    colors: &'a [u8],
+}
+
+impl<'a> TileMap<'a> {
+    /// Map of `columns`×`rows` cells stored row by row
+    ///
+    /// Panics if the map is smaller than the screen or the slices are too short.
+    pub fn new(columns: u16, rows: u16, cells: &'a [u8], colors: &'a [u8]) -> Self {
+        assert!(columns >= COLUMNS && rows >= ROWS);
+        let size = columns as usize * rows as usize;
+        assert!(cells.len() >= size && colors.len() >= size);
+        Self {
+            columns,
+            rows,
+            cells,
+            colors,
+        }
+    }
+
+    pub const fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    pub const fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Largest scroll position in pixels
+    pub const fn max_position(&self) -> (u16, u16) {
+        ((self.columns - COLUMNS) * 8, (self.rows - ROWS) * 8)
+    }
+
+    /// Offset of the cell at `column` and `row`
+    const fn offset(&self, column: u16, row: u16) -> usize {
+        row as usize * self.columns as usize + column as usize
+    }
+}
+
+/// Scroll directions
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    /// Change of the scroll position in x and y for a speed of one
+    pub const fn delta(&self) -> (i8, i8) {
+        match self {
+            Self::Up => (0, -1),
+            Self::UpRight => (1, -1),
+            Self::Right => (1, 0),
+            Self::DownRight => (1, 1),
+            Self::Down => (0, 1),
+            Self::DownLeft => (-1, 1),
+            Self::Left => (-1, 0),
+            Self::UpLeft => (-1, -1),
+        }
+    }
+}
+
+/// Scroll engine with two screens in the active VIC bank
+pub struct ScrollEngine<'a> {
+    map: TileMap<'a>,
+    screens: [ScreenBank; 2],
+    /// Index of the shown screen
+    front: usize,
+    /// Scroll position in pixels
+    x: u16,
+    y: u16,
+    /// Position to show from the next frame, set by `on_middle()`
+    next: (u16, u16),
+    /// Pixels per frame in x and y
+    velocity: (i8, i8),
+    /// Cell drawn in the back screen, if any
+    prepared: Option<(u16, u16)>,
+    /// True if the screens are to be swapped at `BOTTOM_LINE`
+    swap: bool,
+    /// Frames in which the position waited for the back screen
+    stalls: u16,
+}
+
+impl<'a> ScrollEngine<'a> {
+    /// Engine showing `map` from the top left corner using two screens of the active VIC bank
+    ///
+    /// Both 1 KiB screens are overwritten and must be memory reserved for them,
+    /// not holding program code or data, and outside the character ROM shadow.
+    pub fn new(map: TileMap<'a>, screens: [ScreenBank; 2]) -> Self {
+        assert!(screens[0] != screens[1]);
+        Self {
+            map,
+            screens,
+            front: 0,
+            x: 0,
+            y: 0,
+            next: (0, 0),
+            velocity: (0, 0),
+            prepared: None,
+            swap: false,
+            stalls: 0,
+        }
+    }
+
+    /// Select 38 columns and 24 rows and draw the current position
+    ///
+    /// Call before the raster splits are started.
+    pub fn start(&mut self) {
+        unsafe {
+            vic2()
+                .control_x
+                .modify(|r| r.difference(ControlXFlags::COLUMN_SELECT));
+            vic2()
+                .control_y
+                .modify(|r| r.difference(ControlYFlags::ROW_SELECT));
+        }
+        self.redraw();
+    }
+
+    /// Jump to a position in pixels, clamped to the map, and redraw the screen
+    ///
+    /// Must not be called while the raster splits are running.
+    pub fn set_position(&mut self, x: u16, y: u16) {
+        let (max_x, max_y) = self.map.max_position();
+        self.x = x.min(max_x);
+        self.y = y.min(max_y);
+        self.redraw();
+    }
+
+    /// Scroll position in pixels of the top left corner
+    pub const fn get_position(&self) -> (u16, u16) {
+        (self.x, self.y)
+    }
+
+    /// Scroll by `dx` and `dy` pixels (each at most `MAX_SPEED`) in every frame
+    pub fn set_velocity(&mut self, dx: i8, dy: i8) {
+        assert!(dx.unsigned_abs() <= MAX_SPEED && dy.unsigned_abs() <= MAX_SPEED);
+        interrupt::free(|| self.velocity = (dx, dy));
+    }
+
+    pub const fn get_velocity(&self) -> (i8, i8) {
+        self.velocity
+    }
+
+    /// Scroll in `direction` by `speed` pixels per frame; a speed of zero stops scrolling
+    pub fn scroll(&mut self, direction: Direction, speed: u8) {
+        let (dx, dy) = direction.delta();
+        self.set_velocity(dx * speed as i8, dy * speed as i8);
+    }
+
+    /// Number of frames in which scrolling waited for `prepare()`
+    pub const fn stalls(&self) -> u16 {
+        self.stalls
+    }
+
+    /// Absolute address of screen `index`
+    fn screen_address(&self, index: usize) -> u16 {
+        get_vic_bank_address() + ((self.screens[index].bits() as u16) << 6)
+    }
+
+    /// Cell of the top left corner at a position
+    const fn cell_of(position: (u16, u16)) -> (u16, u16) {
+        (position.0 / 8, position.1 / 8)
+    }
+
+    /// Position after one frame at the current velocity, clamped to the map
+    fn advance(&self, (x, y): (u16, u16)) -> (u16, u16) {
+        let (max_x, max_y) = self.map.max_position();
+        let (dx, dy) = self.velocity;
+        (
+            x.saturating_add_signed(dx as i16).min(max_x),
+            y.saturating_add_signed(dy as i16).min(max_y),
+        )
+    }
+
+    /// Cell of the next screen to be shown at the current velocity, if any
+    fn next_cell(&self) -> Option<(u16, u16)> {
+        let cell = Self::cell_of((self.x, self.y));
+        let mut position = (self.x, self.y);
+        for _ in 0..8 {
+            position = self.advance(position);
+            if Self::cell_of(position) != cell {
+                return Some(Self::cell_of(position));
+            }
+        }
+        None
+    }
+
+    /// Copy the screen codes of the map at `cell` to screen `index`
+    fn draw_screen(&self, index: usize, (column, row): (u16, u16)) {
+        let screen = self.screen_address(index) as *mut u8;
+        for i in 0..ROWS {
+            let source = &self.map.cells[self.map.offset(column, row + i)..];
+            unsafe {
+                copy_nonoverlapping(
+                    source.as_ptr(),
+                    screen.add((i * COLUMNS) as usize),
+                    COLUMNS as usize,
+                )
+            };
+        }
+    }
+
+    /// Copy the colors of the map at `cell` to `rows` of color RAM
+    fn draw_colors(&self, (column, row): (u16, u16), rows: Range<u16>) {
+        for i in rows {
+            let source = &self.map.colors[self.map.offset(column, row + i)..];
+            unsafe {
+                copy_nonoverlapping(
+                    source.as_ptr(),
+                    COLOR_RAM.add((i * COLUMNS) as usize),
+                    COLUMNS as usize,
+                )
+            };
+        }
+    }
+
+    /// Show screen `front` and set the fine scroll of the current position
+    fn show(&self) {
+        let fine_x = 7 - (self.x & 7) as u8;
+        let fine_y = 7 - (self.y & 7) as u8;
+        let bank = self.screens[self.front];
+        unsafe {
+            vic2().control_x.modify(|r| r.xscroll(fine_x));
+            vic2().control_y.modify(|r| r.yscroll(fine_y));
+            vic2().screen_and_charset_bank.modify(|r| r.screen(bank));
+        }
+    }
+
+    /// Draw the front screen and all colors at the current position
+    fn redraw(&mut self) {
+        let cell = Self::cell_of((self.x, self.y));
+        self.draw_screen(self.front, cell);
+        self.draw_colors(cell, 0..ROWS);
+        self.next = (self.x, self.y);
+        self.prepared = None;
+        self.swap = false;
+        self.show();
+    }
+
+    /// Draw the back screen for the next cell at the current velocity
+    ///
+    /// Returns `true` if the back screen was drawn, and `false` if it was
+    /// already up to date. Call once per frame from the main program or after
+    /// `on_bottom()`; drawing takes a large part of a frame.
+    pub fn prepare(&mut self) -> bool {
+        if interrupt::free(|| self.swap) {
+            return false;
+        }
+        let cell = match self.next_cell() {
+            Some(cell) if self.prepared != Some(cell) => cell,
+            _ => return false,
+        };
+        interrupt::free(|| self.prepared = None);
+        self.draw_screen(self.front ^ 1, cell);
+        interrupt::free(|| self.prepared = Some(cell));
+        true
+    }
+
+    /// Raster handler for `MIDDLE_LINE`
+    ///
+    /// Advances the position for the next frame. If it enters a new cell, the
+    /// upper half of color RAM is shifted since the beam has passed it.
+    pub fn on_middle(&mut self) {
+        let current = Self::cell_of((self.x, self.y));
+        let (x, y) = self.advance((self.x, self.y));
+        let cell = Self::cell_of((x, y));
+        if cell == current {
+            self.next = (x, y);
+        } else if self.prepared == Some(cell) {
+            self.next = (x, y);
+            self.swap = true;
+            self.draw_colors(cell, 0..SPLIT_ROW);
+        } else {
+            // wait at the edge of the current cell for the back screen
+            let hold = |position: u16, cell: u16| position.clamp(cell * 8, cell * 8 + 7);
+            self.next = (hold(x, current.0), hold(y, current.1));
+            self.stalls = self.stalls.wrapping_add(1);
+        }
+    }
+
+    /// Raster handler for `BOTTOM_LINE`
+    ///
+    /// Applies the position from `on_middle()`, swaps the screens when entering
+    /// a new cell and shifts the lower half of color RAM before the beam reaches it.
+    pub fn on_bottom(&mut self) {
+        (self.x, self.y) = self.next;
+        if self.swap {
+            self.front ^= 1;
+            self.prepared = None;
+            self.swap = false;
+            self.show();
+            self.draw_colors(Self::cell_of((self.x, self.y)), SPLIT_ROW..ROWS);
+        } else {
+            self.show();
+        }
+    }
+}
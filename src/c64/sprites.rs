@@ -0,0 +1,229 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Sprite manager with a shadow buffer
+//!
+//! `SpriteManager` keeps the state of all eight hardware sprites in RAM, where
+//! it can be changed at any time through `Sprite` handles, and writes it to the
+//! VIC-II with `flush()`, _e.g._ from a raster interrupt outside the visible area.
+//! X positions cover the full 0-511 range and shape pointers are written to
+//! the end of the active screen memory, wherever it is.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::sprites::SpriteManager;
+//!
+//! let mut sprites = SpriteManager::new();
+//! sprites
+//!     .sprite(0)
+//!     .set_shape_address(0x2000)
+//!     .set_position(300, 100)
+//!     .set_color(vic2::GREEN)
+//!     .set_expand(true, true)
+//!     .enable();
+//! sprites.flush();
+//! ~~~
+
+use super::layout::SPRITE_POINTERS_OFFSET;
+use super::{get_screen_address, vic2};
+use crate::vic2::Sprites;
+
+/// Number of hardware sprites
+pub const NUM_SPRITES: u8 = 8;
+
+/// Shadow state of a single sprite
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SpriteState {
+    /// X position (0-511)
+    pub x: u16,
+    /// Y position
+    pub y: u8,
+    /// Shape pointer relative to the VIC bank
+    pub pointer: u8,
+    /// Sprite color
+    pub color: u8,
+    pub enabled: bool,
+    /// Double width
+    pub expand_x: bool,
+    /// Double height
+    pub expand_y: bool,
+    pub multicolor: bool,
+    /// Drawn behind foreground graphics
+    pub behind_background: bool,
+}
+
+/// Shadow buffer for all hardware sprites
+#[derive(Clone, Debug, Default)]
+pub struct SpriteManager {
+    sprites: [SpriteState; NUM_SPRITES as usize],
+    /// Shared multicolors (`0xd025`, `0xd026`)
+    multicolors: [u8; 2],
+}
+
+impl SpriteManager {
+    /// New shadow buffer with all sprites disabled
+    pub const fn new() -> Self {
+        const DISABLED: SpriteState = SpriteState {
+            x: 0,
+            y: 0,
+            pointer: 0,
+            color: 0,
+            enabled: false,
+            expand_x: false,
+            expand_y: false,
+            multicolor: false,
+            behind_background: false,
+        };
+        Self {
+            sprites: [DISABLED; NUM_SPRITES as usize],
+            multicolors: [0, 0],
+        }
+    }
+
+    /// Handle to sprite `index` (0-7)
+    pub fn sprite(&mut self, index: u8) -> Sprite<'_> {
+        assert!(index < NUM_SPRITES);
+        Sprite {
+            state: &mut self.sprites[index as usize],
+        }
+    }
+
+    /// Shadow state of sprite `index` (0-7)
+    pub fn state(&self, index: u8) -> &SpriteState {
+        &self.sprites[index as usize]
+    }
+
+    /// Set the two colors shared by all multicolor sprites
+    pub fn set_multicolors(&mut self, color0: u8, color1: u8) {
+        self.multicolors = [color0, color1];
+    }
+
+    /// Bitmask of the sprites for which `f` returns true
+    fn mask<F: Fn(&SpriteState) -> bool>(&self, f: F) -> Sprites {
+        self.sprites
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| f(state))
+            .fold(Sprites::empty(), |mask, (i, _)| {
+                mask | Sprites::new(i as u8)
+            })
+    }
+
+    /// Write the shadow buffer to the VIC-II and the sprite pointers of the active screen
+    pub fn flush(&self) {
+        self.flush_to(get_screen_address());
+    }
+
+    /// Write the shadow buffer to the VIC-II, with sprite pointers in screen memory at `screen`
+    ///
+    /// Useful when double buffering screens.
+    pub fn flush_to(&self, screen: u16) {
+        let vic = vic2();
+        let pointers = (screen + SPRITE_POINTERS_OFFSET) as *mut u8;
+        unsafe {
+            for (i, state) in self.sprites.iter().enumerate() {
+                vic.sprite_positions[i].x.write(state.x as u8);
+                vic.sprite_positions[i].y.write(state.y);
+                vic.sprite_colors[i].write(state.color);
+                pointers.add(i).write_volatile(state.pointer);
+            }
+            vic.sprite_positions_most_significant_bit_of_x
+                .write(self.mask(|s| s.x > 0xff));
+            vic.sprite_expand_x.write(self.mask(|s| s.expand_x));
+            vic.sprite_expand_y.write(self.mask(|s| s.expand_y));
+            vic.sprite_multicolor_mode
+                .write(self.mask(|s| s.multicolor));
+            vic.sprite_background_priority
+                .write(self.mask(|s| s.behind_background));
+            vic.sprite_multicolor0.write(self.multicolors[0]);
+            vic.sprite_multicolor1.write(self.multicolors[1]);
+            vic.sprite_enable.write(self.mask(|s| s.enabled));
+        }
+    }
+}
+
+/// Handle to one sprite in a `SpriteManager`; setters can be chained
+pub struct Sprite<'a> {
+    state: &'a mut SpriteState,
+}
+
+impl Sprite<'_> {
+    /// Set X (0-511) and Y position
+    pub fn set_position(&mut self, x: u16, y: u8) -> &mut Self {
+        self.state.x = x & 0x1ff;
+        self.state.y = y;
+        self
+    }
+
+    /// X (0-511) and Y position
+    pub fn get_position(&self) -> (u16, u8) {
+        (self.state.x, self.state.y)
+    }
+
+    /// Set shape pointer relative to the VIC bank, see `vic2::to_sprite_pointer()`
+    pub fn set_shape(&mut self, pointer: u8) -> &mut Self {
+        self.state.pointer = pointer;
+        self
+    }
+
+    /// Set shape from the absolute, 64 byte aligned address of the sprite data
+    ///
+    /// The address must be in the VIC bank that is active when flushing.
+    pub fn set_shape_address(&mut self, address: u16) -> &mut Self {
+        assert!(address.is_multiple_of(64));
+        self.set_shape(((address % 0x4000) / 64) as u8)
+    }
+
+    /// Set sprite color
+    pub fn set_color(&mut self, color: u8) -> &mut Self {
+        self.state.color = color;
+        self
+    }
+
+    /// Set double width and double height
+    pub fn set_expand(&mut self, x: bool, y: bool) -> &mut Self {
+        self.state.expand_x = x;
+        self.state.expand_y = y;
+        self
+    }
+
+    /// Set multicolor mode with the colors from `SpriteManager::set_multicolors()`
+    pub fn set_multicolor(&mut self, multicolor: bool) -> &mut Self {
+        self.state.multicolor = multicolor;
+        self
+    }
+
+    /// Draw the sprite behind (true) or in front of (false) foreground graphics
+    pub fn set_behind_background(&mut self, behind: bool) -> &mut Self {
+        self.state.behind_background = behind;
+        self
+    }
+
+    /// Show the sprite
+    pub fn enable(&mut self) -> &mut Self {
+        self.state.enabled = true;
+        self
+    }
+
+    /// Hide the sprite
+    pub fn disable(&mut self) -> &mut Self {
+        self.state.enabled = false;
+        self
+    }
+
+    /// Shadow state of the sprite
+    pub fn state(&self) -> &SpriteState {
+        self.state
+    }
+}
@@ -0,0 +1,281 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Text screen with cursor, colors, scrolling and formatted output
+//!
+//! `TextScreen` writes screen codes directly to screen memory and colors to
+//! color RAM, without the KERNAL. Text is converted with
+//! `petscii::Petscii::to_screen_code()` for the mixed case charset, and
+//! characters without a PETSCII equivalent are shown as `?`. Output wraps at
+//! the end of a line and scrolls within the scroll region.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::c64::text_screen::TextScreen;
+//! use ufmt::uwrite;
+//!
+//! let mut screen = TextScreen::default();
+//! screen.clear();
+//! screen.set_color(vic2::YELLOW);
+//! screen.set_cursor(10, 2);
+//! uwrite!(screen, "score: {}", 1200).unwrap();
+//! screen.set_scroll_region(5, 25);
+//! core::fmt::Write::write_str(&mut screen, "scrolls below row 5\n").unwrap();
+//! ~~~
+
+use super::{get_vic_bank_address, COLOR_RAM};
+use crate::petscii::Petscii;
+use crate::vic2::{ScreenBank, LIGHT_BLUE};
+use core::convert::Infallible;
+
+/// Number of columns
+pub const COLUMNS: u8 = 40;
+/// Number of rows
+pub const ROWS: u8 = 25;
+/// Screen code of a space
+const SPACE: u8 = 0x20;
+/// Screen code for characters without a PETSCII equivalent
+const UNKNOWN: u8 = 0x3f;
+/// Bit that selects the reversed glyph
+const REVERSE: u8 = 0x80;
+
+/// 40×25 text screen in screen memory at any 1 KiB boundary
+pub struct TextScreen {
+    screen: *mut u8,
+    colors: *mut u8,
+    column: u8,
+    row: u8,
+    color: u8,
+    reverse: bool,
+    /// First row of the scroll region
+    top: u8,
+    /// Row after the scroll region
+    bottom: u8,
+}
+
+impl TextScreen {
+    /// Text screen at `bank` relative to the active VIC bank
+    ///
+    /// # Safety
+    ///
+    /// As for `at_address()`; the slot may hold program code or data.
+    pub unsafe fn new(bank: ScreenBank) -> Self {
+        Self::at_address(get_vic_bank_address() + ((bank.bits() as u16) << 6))
+    }
+
+    /// Text screen in screen memory at absolute address `screen`
+    ///
+    /// # Safety
+    ///
+    /// The 1000 bytes at `screen` are overwritten by the safe methods and must be
+    /// valid and not otherwise in use for the lifetime of the text screen.
+    pub const unsafe fn at_address(screen: u16) -> Self {
+        Self {
+            screen: screen as *mut u8,
+            colors: COLOR_RAM,
+            column: 0,
+            row: 0,
+            color: LIGHT_BLUE,
+            reverse: false,
+            top: 0,
+            bottom: ROWS,
+        }
+    }
+
+    /// Address of screen memory
+    pub fn get_address(&self) -> u16 {
+        self.screen as u16
+    }
+
+    /// Move the cursor; coordinates are clamped to the screen
+    pub fn set_cursor(&mut self, column: u8, row: u8) {
+        self.column = column.min(COLUMNS - 1);
+        self.row = row.min(ROWS - 1);
+    }
+
+    /// Cursor column and row
+    pub const fn get_cursor(&self) -> (u8, u8) {
+        (self.column, self.row)
+    }
+
+    /// Set the color of following output
+    pub fn set_color(&mut self, color: u8) {
+        self.color = color;
+    }
+
+    pub const fn get_color(&self) -> u8 {
+        self.color
+    }
+
+    /// Enable or disable reverse video for following output
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.reverse = reverse;
+    }
+
+    pub const fn is_reverse(&self) -> bool {
+        self.reverse
+    }
+
+    /// Limit scrolling to rows `top` up to, but not including, `bottom`
+    ///
+    /// The cursor is moved to the start of the region.
+    pub fn set_scroll_region(&mut self, top: u8, bottom: u8) {
+        assert!(top < bottom && bottom <= ROWS);
+        self.top = top;
+        self.bottom = bottom;
+        self.set_cursor(0, top);
+    }
+
+    /// Scroll region as `(top, bottom)` rows
+    pub const fn get_scroll_region(&self) -> (u8, u8) {
+        (self.top, self.bottom)
+    }
+
+    /// Offset of a cell in screen memory and color RAM
+    const fn offset(column: u8, row: u8) -> usize {
+        row as usize * COLUMNS as usize + column as usize
+    }
+
+    /// Write a screen code and color at a position without moving the cursor
+    pub fn set_cell(&mut self, column: u8, row: u8, screen_code: u8, color: u8) {
+        assert!(column < COLUMNS && row < ROWS);
+        let offset = Self::offset(column, row);
+        unsafe {
+            self.screen.add(offset).write_volatile(screen_code);
+            self.colors.add(offset).write_volatile(color);
+        }
+    }
+
+    /// Screen code and color at a position
+    pub fn get_cell(&self, column: u8, row: u8) -> (u8, u8) {
+        assert!(column < COLUMNS && row < ROWS);
+        let offset = Self::offset(column, row);
+        unsafe {
+            (
+                self.screen.add(offset).read_volatile(),
+                self.colors.add(offset).read_volatile() & 0x0f,
+            )
+        }
+    }
+
+    /// Fill rows `first..last` with spaces in the current color
+    fn clear_rows(&mut self, first: u8, last: u8) {
+        let start = Self::offset(0, first);
+        let end = Self::offset(0, last);
+        unsafe {
+            for offset in start..end {
+                self.screen.add(offset).write_volatile(SPACE);
+                self.colors.add(offset).write_volatile(self.color);
+            }
+        }
+    }
+
+    /// Clear the whole screen with the current color and move the cursor to the
+    /// start of the scroll region
+    pub fn clear(&mut self) {
+        self.clear_rows(0, ROWS);
+        self.set_cursor(0, self.top);
+    }
+
+    /// Copy a row in screen memory and color RAM
+    fn copy_row(&mut self, from: u8, to: u8) {
+        let (source, destination) = (Self::offset(0, from), Self::offset(0, to));
+        unsafe {
+            for i in 0..COLUMNS as usize {
+                let code = self.screen.add(source + i).read_volatile();
+                let color = self.colors.add(source + i).read_volatile();
+                self.screen.add(destination + i).write_volatile(code);
+                self.colors.add(destination + i).write_volatile(color);
+            }
+        }
+    }
+
+    /// Scroll the scroll region up one row and clear its last row
+    pub fn scroll_up(&mut self) {
+        (self.top..self.bottom - 1).for_each(|row| self.copy_row(row + 1, row));
+        self.clear_rows(self.bottom - 1, self.bottom);
+    }
+
+    /// Scroll the scroll region down one row and clear its first row
+    pub fn scroll_down(&mut self) {
+        (self.top + 1..self.bottom)
+            .rev()
+            .for_each(|row| self.copy_row(row - 1, row));
+        self.clear_rows(self.top, self.top + 1);
+    }
+
+    /// Move the cursor to the start of the next line, scrolling at the end of the region
+    pub fn newline(&mut self) {
+        self.column = 0;
+        if self.row + 1 >= self.bottom {
+            self.scroll_up();
+        } else {
+            self.row += 1;
+        }
+    }
+
+    /// Write a screen code at the cursor and advance it
+    pub fn put_screen_code(&mut self, screen_code: u8) {
+        let code = match self.reverse {
+            true => screen_code | REVERSE,
+            false => screen_code,
+        };
+        self.set_cell(self.column, self.row, code, self.color);
+        self.column += 1;
+        if self.column >= COLUMNS {
+            self.newline();
+        }
+    }
+
+    /// Write a character at the cursor; `\n` starts a new line and `\r` returns to column 0
+    pub fn put_char(&mut self, letter: char) {
+        match letter {
+            '\n' => self.newline(),
+            '\r' => self.column = 0,
+            _ => {
+                let code = Petscii::try_from_char(letter)
+                    .map(|petscii| petscii.to_screen_code())
+                    .unwrap_or(UNKNOWN);
+                self.put_screen_code(code);
+            }
+        }
+    }
+
+    /// Write a string at the cursor
+    pub fn put_str(&mut self, text: &str) {
+        text.chars().for_each(|letter| self.put_char(letter));
+    }
+}
+
+impl Default for TextScreen {
+    /// Text screen at 0x0400, the screen memory after reset
+    fn default() -> Self {
+        unsafe { Self::at_address(0x0400) }
+    }
+}
+
+impl ufmt_write::uWrite for TextScreen {
+    type Error = Infallible;
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+        self.put_str(text);
+        Ok(())
+    }
+}
+
+impl core::fmt::Write for TextScreen {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        self.put_str(text);
+        Ok(())
+    }
+}
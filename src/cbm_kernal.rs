@@ -252,7 +252,7 @@ fn _cbm_load(filename: &CStr, device: u8, load_address: Option<u16>) -> u16 {
     unsafe {
         cbm_k_setlfs(lfn, device, secondary_address);
         cbm_k_setnam(filename.to_bytes_with_nul().as_ptr());
-        cbm_k_load(lfn, address) - address
+        cbm_k_load(lfn, address as _) as u16 - address
     }
 }
 
@@ -0,0 +1,253 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Custom character sets
+//!
+//! A `Charset` holds 256 glyphs of 8×8 pixels, each glyph being eight bytes
+//! with one byte per row and the leftmost pixel in the most significant bit.
+//! Charsets can be copied from the character ROM, edited glyph by glyph, and
+//! installed at a `CharsetBank` of the VIC bank seen by the VIC-II, or anywhere
+//! in the first 64 KiB on the MEGA65.
+//!
+//! The character ROM is read differently on the C64 and MEGA65, so with both
+//! features enabled, as by default, use `from_c64_rom()` or `from_mega65_rom()`
+//! instead of `from_rom()`.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::charset::{self, Charset, RomCharset};
+//!
+//! let mut font = Charset::from_c64_rom(RomCharset::Mixed);
+//! font.transform(0x01..=0x1a, charset::bold); // bold a-z
+//! font.set_glyph(0x00, [0x3c, 0x42, 0xa5, 0x81, 0xa5, 0x99, 0x42, 0x3c]);
+//! unsafe { font.install(c64::vic2(), c64::get_vic_bank_address(), vic2::CharsetBank::AT_2000) };
+//! ~~~
+
+use crate::vic2::{CharsetBank, MOSVideoInterfaceControllerII};
+use core::ops::RangeInclusive;
+
+/// Number of glyphs in a charset
+pub const GLYPHS: usize = 256;
+/// Size of a charset in bytes
+pub const CHARSET_SIZE: usize = GLYPHS * 8;
+/// Address of the C64 character ROM in MEGA65 memory
+pub const MEGA65_CHAR_ROM: u32 = 0x2d000;
+
+/// Pixel rows of one glyph
+pub type Glyph = [u8; 8];
+
+/// Charsets in the character ROM
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RomCharset {
+    /// Upper case and graphics (offset 0x000)
+    Upper,
+    /// Mixed upper and lower case (offset 0x800)
+    Mixed,
+}
+
+impl RomCharset {
+    /// Offset in the character ROM
+    pub const fn offset(&self) -> u16 {
+        match self {
+            Self::Upper => 0x0000,
+            Self::Mixed => 0x0800,
+        }
+    }
+}
+
+/// Thicken a glyph by one pixel to the right
+pub const fn bold(glyph: Glyph) -> Glyph {
+    let mut result = glyph;
+    let mut row = 0;
+    while row < 8 {
+        result[row] |= glyph[row] >> 1;
+        row += 1;
+    }
+    result
+}
+
+/// Slant a glyph by shifting the upper half one pixel to the right
+pub const fn italic(glyph: Glyph) -> Glyph {
+    let mut result = glyph;
+    let mut row = 0;
+    while row < 4 {
+        result[row] = glyph[row] >> 1;
+        row += 1;
+    }
+    result
+}
+
+/// Swap foreground and background pixels
+pub const fn inverse(glyph: Glyph) -> Glyph {
+    let mut result = glyph;
+    let mut row = 0;
+    while row < 8 {
+        result[row] = !glyph[row];
+        row += 1;
+    }
+    result
+}
+
+/// Set of 256 glyphs
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Charset {
+    data: [u8; CHARSET_SIZE],
+}
+
+impl Charset {
+    /// Charset with all glyphs blank
+    pub const fn new() -> Self {
+        Self {
+            data: [0; CHARSET_SIZE],
+        }
+    }
+
+    /// Charset from raw glyph data
+    pub const fn from_bytes(data: [u8; CHARSET_SIZE]) -> Self {
+        Self { data }
+    }
+
+    /// Copy a charset from the character ROM of the only enabled platform
+    ///
+    /// Only available with one of the `c64` and `mega65` features.
+    #[cfg(all(feature = "c64", not(feature = "mega65")))]
+    pub fn from_rom(rom: RomCharset) -> Self {
+        Self::from_c64_rom(rom)
+    }
+
+    /// Copy a charset from the character ROM of the only enabled platform
+    ///
+    /// Only available with one of the `c64` and `mega65` features.
+    #[cfg(all(feature = "mega65", not(feature = "c64")))]
+    pub fn from_rom(rom: RomCharset) -> Self {
+        Self::from_mega65_rom(rom)
+    }
+
+    /// Copy a charset from the C64 character ROM, banked in through the CPU port
+    #[cfg(feature = "c64")]
+    pub fn from_c64_rom(rom: RomCharset) -> Self {
+        let mut charset = Self::new();
+        crate::c64::banking::copy_char_rom(rom.offset(), &mut charset.data);
+        charset
+    }
+
+    /// Copy a charset from the C64 character ROM in MEGA65 memory at `MEGA65_CHAR_ROM`
+    #[cfg(feature = "mega65")]
+    pub fn from_mega65_rom(rom: RomCharset) -> Self {
+        Self::from_far_memory(MEGA65_CHAR_ROM + rom.offset() as u32)
+    }
+
+    /// Copy a charset from 28-bit MEGA65 memory, _e.g._ `MEGA65_CHAR_ROM + RomCharset::Mixed.offset()`
+    #[cfg(feature = "mega65")]
+    pub fn from_far_memory(address: u32) -> Self {
+        let mut charset = Self::new();
+        for (i, byte) in charset.data.iter_mut().enumerate() {
+            *byte = crate::mega65::lpeek(address + i as u32);
+        }
+        charset
+    }
+
+    /// Raw glyph data
+    pub const fn as_bytes(&self) -> &[u8; CHARSET_SIZE] {
+        &self.data
+    }
+
+    /// Rows of glyph `index`
+    pub fn get_glyph(&self, index: u8) -> Glyph {
+        let start = index as usize * 8;
+        let mut glyph = [0; 8];
+        glyph.copy_from_slice(&self.data[start..start + 8]);
+        glyph
+    }
+
+    /// Replace glyph `index`
+    pub fn set_glyph(&mut self, index: u8, glyph: Glyph) {
+        let start = index as usize * 8;
+        self.data[start..start + 8].copy_from_slice(&glyph);
+    }
+
+    /// Replace each glyph in `indices` by `f` applied to it, _e.g._ `bold`
+    pub fn transform<F: Fn(Glyph) -> Glyph>(&mut self, indices: RangeInclusive<u8>, f: F) {
+        for index in indices {
+            self.set_glyph(index, f(self.get_glyph(index)));
+        }
+    }
+
+    /// Copy the charset to `address`
+    ///
+    /// # Safety
+    ///
+    /// Writes 2 KiB of memory starting at `address`.
+    pub unsafe fn copy_to(&self, address: u16) {
+        let destination = address as *mut u8;
+        for (i, byte) in self.data.iter().enumerate() {
+            destination.add(i).write_volatile(*byte);
+        }
+    }
+
+    /// Copy the charset to `bank` in the VIC bank starting at `vic_bank_address` and select it
+    ///
+    /// Selects the charset through the VIC-II memory setup register, so on the
+    /// MEGA65 use `install_mega65()` instead. The VIC bank address is
+    /// `c64::get_vic_bank_address()`. Panics if the slot is hidden by the character
+    /// ROM at 0x1000-0x1fff in VIC banks 0 and 2.
+    ///
+    /// # Safety
+    ///
+    /// Overwrites 2 KiB of memory in the VIC bank.
+    pub unsafe fn install(
+        &self,
+        vic: &MOSVideoInterfaceControllerII,
+        vic_bank_address: u16,
+        bank: CharsetBank,
+    ) {
+        let address = vic_bank_address + ((bank.bits() as u16) << 10);
+        assert!(
+            !in_char_rom_shadow(address),
+            "charset slot shows the character ROM"
+        );
+        self.copy_to(address);
+        vic.screen_and_charset_bank.modify(|r| r.charset(bank));
+    }
+
+    /// Copy the charset to `address` and point the VIC-IV charset pointer at it
+    ///
+    /// Any 2 KiB aligned address outside the character ROM shadow at 0x1000-0x1fff
+    /// and 0x9000-0x9fff can be used, independent of the VIC bank.
+    ///
+    /// # Safety
+    ///
+    /// Writes 2 KiB of memory starting at `address`.
+    #[cfg(feature = "mega65")]
+    pub unsafe fn install_mega65(&self, address: u16) {
+        assert!(address.is_multiple_of(CHARSET_SIZE as u16));
+        assert!(
+            !in_char_rom_shadow(address),
+            "charset address shows the character ROM"
+        );
+        self.copy_to(address);
+        crate::mega65::set_charset_address(address);
+    }
+}
+
+/// True if a charset at `address` is hidden by the character ROM in VIC banks 0 and 2
+const fn in_char_rom_shadow(address: u16) -> bool {
+    address & 0x4000 == 0 && address & 0x3000 == 0x1000
+}
+
+impl Default for Charset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -20,10 +20,11 @@
 //! Time-of-Day (TOD) clock. The device's most prominent use was in the Commodore 64
 //! and Commodore 128(D), each of which included two CIA chips.
 
+use crate::bus::RW;
+use crate::detect::VideoStandard;
 use bitflags::bitflags;
 use core::mem::size_of;
 use static_assertions::const_assert;
-use volatile_register::RW;
 
 // === COMMON ==============================
 
@@ -90,16 +91,43 @@ impl TimeOfDay {
 
         let hours = if pm { hours | 0x80 } else { hours };
 
+        // writing hours stops the clock until tenths are written
         unsafe {
-            self.tenths.write(tenths);
-            self.seconds.write(seconds);
-            self.minutes.write(minutes);
             self.hours.write(hours);
+            self.minutes.write(minutes);
+            self.seconds.write(seconds);
+            self.tenths.write(tenths);
         }
 
         true
     }
 
+    /// Read time as a consistent snapshot
+    ///
+    /// Reading hours latches all registers until tenths are read, so the
+    /// registers are read from hours to tenths.
+    pub fn read_time(&self) -> TodTime {
+        let hours = self.hours.read();
+        let minutes = self.minutes.read();
+        let seconds = self.seconds.read();
+        let tenths = self.tenths.read();
+        TodTime::from_bcd(hours, minutes, seconds, tenths)
+    }
+
+    /// Write time, or the alarm if selected in control register B
+    ///
+    /// Writing hours stops the clock until tenths are written, so the
+    /// registers are written from hours to tenths.
+    pub fn write_time(&self, time: TodTime) {
+        let [hours, minutes, seconds, tenths] = time.to_bcd();
+        unsafe {
+            self.hours.write(hours);
+            self.minutes.write(minutes);
+            self.seconds.write(seconds);
+            self.tenths.write(tenths);
+        }
+    }
+
     pub fn is_pm(&self) -> bool {
         (self.hours.read() & 0x80) != 0
     }
@@ -121,6 +149,132 @@ impl TimeOfDay {
     }
 }
 
+/// Convert binary value 0-99 to BCD
+pub const fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+/// Convert BCD value to binary
+pub const fn from_bcd(bcd: u8) -> u8 {
+    (bcd >> 4) * 10 + (bcd & 0x0f)
+}
+
+/// Time-of-day clock value in the 12-hour AM/PM format of the CIA
+///
+/// # Examples
+/// ~~~
+/// let time = TodTime::from_24h(13, 37, 0, 0).unwrap();
+/// assert_eq!(time.hours, 1);
+/// assert!(time.pm);
+/// c64::cia1().control.set_tod_time(time);
+/// ~~~
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TodTime {
+    /// Hours 1-12
+    pub hours: u8,
+    /// Minutes 0-59
+    pub minutes: u8,
+    /// Seconds 0-59
+    pub seconds: u8,
+    /// Tenths of a second 0-9
+    pub tenths: u8,
+    /// Afternoon flag
+    pub pm: bool,
+}
+
+impl TodTime {
+    /// Midnight, 12:00:00.0 AM
+    pub const MIDNIGHT: Self = Self {
+        hours: 12,
+        minutes: 0,
+        seconds: 0,
+        tenths: 0,
+        pm: false,
+    };
+
+    /// New time from decimal values; `None` if out of range
+    pub const fn new(hours: u8, minutes: u8, seconds: u8, tenths: u8, pm: bool) -> Option<Self> {
+        if hours == 0 || hours > 12 || minutes > 59 || seconds > 59 || tenths > 9 {
+            return None;
+        }
+        Some(Self {
+            hours,
+            minutes,
+            seconds,
+            tenths,
+            pm,
+        })
+    }
+
+    /// New time from 24-hour decimal values, `hours` 0-23; `None` if out of range
+    pub const fn from_24h(hours: u8, minutes: u8, seconds: u8, tenths: u8) -> Option<Self> {
+        if hours > 23 {
+            return None;
+        }
+        let hours_12 = match hours % 12 {
+            0 => 12,
+            h => h,
+        };
+        Self::new(hours_12, minutes, seconds, tenths, hours >= 12)
+    }
+
+    /// Hours in 24-hour format, 0-23
+    pub const fn hours_24(&self) -> u8 {
+        (self.hours % 12) + if self.pm { 12 } else { 0 }
+    }
+
+    /// Time since midnight in tenths of a second
+    pub const fn as_tenths(&self) -> u32 {
+        let seconds =
+            (self.hours_24() as u32 * 60 + self.minutes as u32) * 60 + self.seconds as u32;
+        seconds * 10 + self.tenths as u32
+    }
+
+    /// New time from raw register values (hours with PM in bit 7, minutes, seconds, tenths)
+    ///
+    /// Values are not validated; use `is_valid()` to check.
+    pub const fn from_bcd(hours: u8, minutes: u8, seconds: u8, tenths: u8) -> Self {
+        Self {
+            hours: from_bcd(hours & 0x1f),
+            minutes: from_bcd(minutes & 0x7f),
+            seconds: from_bcd(seconds & 0x7f),
+            tenths: tenths & 0x0f,
+            pm: hours & 0x80 != 0,
+        }
+    }
+
+    /// Raw register values: hours with PM in bit 7, minutes, seconds, and tenths
+    pub const fn to_bcd(&self) -> [u8; 4] {
+        let pm = if self.pm { 0x80 } else { 0 };
+        [
+            to_bcd(self.hours) | pm,
+            to_bcd(self.minutes),
+            to_bcd(self.seconds),
+            self.tenths,
+        ]
+    }
+
+    /// True if all fields are in range
+    pub const fn is_valid(&self) -> bool {
+        Self::new(self.hours, self.minutes, self.seconds, self.tenths, self.pm).is_some()
+    }
+}
+
+impl Default for TodTime {
+    fn default() -> Self {
+        Self::MIDNIGHT
+    }
+}
+
+/// Mains frequency driving the time-of-day clock
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TodFrequency {
+    /// 50 Hz, _e.g._ PAL machines
+    Hz50,
+    /// 60 Hz, _e.g._ NTSC machines
+    Hz60,
+}
+
 bitflags! {
     /// CIA Interrupt Control Register (ICR)
     ///
@@ -130,16 +284,18 @@ bitflags! {
     pub struct InterruptControl: u8 {
         /// Bit 7: Set (1) or Clear (0) mode for writing
         const SET_CLEAR        = 0b1000_0000;
+        /// Bit 7: An enabled source has requested an interrupt (read only)
+        const IRQ              = 0b1000_0000;
         /// Bit 4: FLAG pin interrupt (external input)
         const FLAG             = 0b0001_0000;
         /// Bit 3: Serial Port interrupt
         const SERIAL           = 0b0000_1000;
-        /// Bit 2: Timer A underflow interrupt
-        const TIMER_A          = 0b0000_0100;
+        /// Bit 2: Time-of-day alarm interrupt
+        const TOD_ALARM        = 0b0000_0100;
         /// Bit 1: Timer B underflow interrupt
         const TIMER_B          = 0b0000_0010;
-        /// Bit 0: IRQ occurred (read only)
-        const IRQ              = 0b0000_0001;
+        /// Bit 0: Timer A underflow interrupt
+        const TIMER_A          = 0b0000_0001;
         /// Disable all irq
         const DISABLE_ALL      = 0b0111_1111;
     }
@@ -158,18 +314,71 @@ bitflags! {
         const RUNMODE          = 0b0000_1000;
         /// Bit 4: LOAD - Force timer reload (strobe)
         const LOAD             = 0b0001_0000;
-        /// Bit 5: INMODE - Clock source Timer A (0=PHI2, 1=CNT)
-        ///        or for Timer B (0=PHI2, 1=Timer A underflow)
+        /// Bit 5: INMODE - Clock source (0=PHI2, 1=CNT)
         const INMODE           = 0b0010_0000;
         /// Bit 6: SPMODE - Serial Port mode (Timer A only)
         const SPMODE           = 0b0100_0000;
+        /// Bit 6: Timer B counts Timer A underflows instead of PHI2 or CNT (Timer B only)
+        const COUNT_TIMER_A    = 0b0100_0000;
         /// Bit 7: TODIN - 50/60Hz TOD input (Timer A only)
         const TODIN            = 0b1000_0000;
+        /// Bit 7: ALARM - Writes to the TOD registers set the alarm instead of the clock (Timer B only)
+        const ALARM            = 0b1000_0000;
     }
 }
 
 pub const TIMER_OFF: TimerControl = TimerControl::empty();
 
+/// System clock of a PAL C64 in Hz, driving the CIA timers
+pub const PAL_CLOCK_HZ: u32 = VideoStandard::Pal.clock_hz();
+
+/// System clock of an NTSC C64 in Hz, driving the CIA timers
+pub const NTSC_CLOCK_HZ: u32 = VideoStandard::Ntsc.clock_hz();
+
+/// System clock of a Drean (PAL-N) C64 in Hz, driving the CIA timers
+pub const DREAN_CLOCK_HZ: u32 = VideoStandard::Drean.clock_hz();
+
+/// Convert microseconds to clock cycles for a system clock in Hz
+///
+/// # Examples
+/// ~~~
+/// let cycles = cia::micros_to_cycles(20_000, cia::PAL_CLOCK_HZ); // 19704
+/// ~~~
+pub const fn micros_to_cycles(micros: u32, clock_hz: u32) -> u32 {
+    (micros as u64 * clock_hz as u64 / 1_000_000) as u32
+}
+
+/// Convert clock cycles to microseconds for a system clock in Hz
+pub const fn cycles_to_micros(cycles: u32, clock_hz: u32) -> u32 {
+    (cycles as u64 * 1_000_000 / clock_hz as u64) as u32
+}
+
+/// One of the two interval timers of a CIA
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Timer {
+    A,
+    B,
+}
+
+impl Timer {
+    /// Interrupt source of the timer
+    pub const fn interrupt(self) -> InterruptControl {
+        match self {
+            Timer::A => InterruptControl::TIMER_A,
+            Timer::B => InterruptControl::TIMER_B,
+        }
+    }
+}
+
+/// Whether a timer stops or reloads and continues on underflow
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TimerMode {
+    /// Stop on underflow
+    OneShot,
+    /// Reload from the latch and continue on underflow
+    Continuous,
+}
+
 // === CIA ================================
 
 /// MOSComplexInterfaceAdapter6526 control part
@@ -193,6 +402,200 @@ pub struct Mos6526ControlBlock {
 
 const_assert!(size_of::<Mos6526ControlBlock>() == 12);
 
+impl Mos6526ControlBlock {
+    /// Timer counter register
+    const fn timer(&self, timer: Timer) -> &RW<u16> {
+        match timer {
+            Timer::A => &self.timer_a,
+            Timer::B => &self.timer_b,
+        }
+    }
+
+    /// Timer control register
+    const fn timer_control(&self, timer: Timer) -> &RW<TimerControl> {
+        match timer {
+            Timer::A => &self.control_a,
+            Timer::B => &self.control_b,
+        }
+    }
+
+    /// Count down `cycles` on the system clock; underflows after `cycles + 1` cycles
+    ///
+    /// The TOD and serial port settings in the control register are kept.
+    ///
+    /// # Examples
+    /// ~~~
+    /// let cycles = cia::micros_to_cycles(10_000, cia::PAL_CLOCK_HZ) as u16;
+    /// c64::cia1().control.start_timer(Timer::B, cycles, TimerMode::OneShot);
+    /// c64::cia1().control.wait_timer(Timer::B); // wait 10 ms
+    /// ~~~
+    pub fn start_timer(&self, timer: Timer, cycles: u16, mode: TimerMode) {
+        let keep = match timer {
+            Timer::A => TimerControl::TODIN | TimerControl::SPMODE,
+            Timer::B => TimerControl::ALARM,
+        };
+        let run_mode = match mode {
+            TimerMode::OneShot => TimerControl::RUNMODE,
+            TimerMode::Continuous => TimerControl::empty(),
+        };
+        let control = self.timer_control(timer);
+        unsafe {
+            control.modify(|v| v & keep);
+            self.timer(timer).write(cycles);
+            control.modify(|v| v | run_mode | TimerControl::LOAD | TimerControl::START);
+        }
+    }
+
+    /// Stop `timer` without changing its counter
+    pub fn stop_timer(&self, timer: Timer) {
+        unsafe {
+            self.timer_control(timer)
+                .modify(|v| v & !TimerControl::START);
+        }
+    }
+
+    /// True if `timer` is running; a one-shot timer stops on underflow
+    pub fn is_timer_running(&self, timer: Timer) -> bool {
+        self.timer_control(timer)
+            .read()
+            .contains(TimerControl::START)
+    }
+
+    /// Busy wait until a one-shot `timer` has underflowed
+    pub fn wait_timer(&self, timer: Timer) {
+        while self.is_timer_running(timer) {}
+    }
+
+    /// Current counter of `timer`
+    ///
+    /// The two bytes are not latched by the CIA, so a read that straddles a
+    /// borrow from the low byte is detected and repeated.
+    pub fn timer_value(&self, timer: Timer) -> u16 {
+        let counter = self.timer(timer);
+        let first = counter.read();
+        let second = counter.read();
+        match first >= second && first - second < 0x80 {
+            true => second,
+            false => counter.read(),
+        }
+    }
+
+    /// Raise an interrupt when `timer` underflows, or stop doing so
+    ///
+    /// Note that the interrupt control register is shared with other sources
+    /// and that reading it acknowledges all of them.
+    pub fn set_timer_interrupt(&self, timer: Timer, enabled: bool) {
+        let flags = match enabled {
+            true => InterruptControl::SET_CLEAR | timer.interrupt(),
+            false => timer.interrupt(),
+        };
+        unsafe { self.interrupt.write(flags) };
+    }
+
+    /// Start a 32-bit stopwatch counting system clock cycles
+    ///
+    /// Timer A counts cycles and timer B counts timer A underflows, so both timers
+    /// are used. Read with `stopwatch_cycles()`.
+    ///
+    /// # Examples
+    /// ~~~
+    /// let cia = &c64::cia2().control;
+    /// cia.start_stopwatch();
+    /// // ...code to profile...
+    /// let micros = cia::cycles_to_micros(cia.stopwatch_cycles(), cia::PAL_CLOCK_HZ);
+    /// ~~~
+    pub fn start_stopwatch(&self) {
+        unsafe {
+            self.control_a
+                .modify(|v| v & (TimerControl::TODIN | TimerControl::SPMODE));
+            self.control_b.modify(|v| v & TimerControl::ALARM);
+            self.timer_a.write(u16::MAX);
+            self.timer_b.write(u16::MAX);
+            self.control_b.modify(|v| {
+                v | TimerControl::COUNT_TIMER_A | TimerControl::LOAD | TimerControl::START
+            });
+            self.control_a
+                .modify(|v| v | TimerControl::LOAD | TimerControl::START);
+        }
+    }
+
+    /// Stop both timers of the stopwatch; the elapsed cycles can still be read
+    pub fn stop_stopwatch(&self) {
+        self.stop_timer(Timer::A);
+        self.stop_timer(Timer::B);
+    }
+
+    /// Cycles elapsed since `start_stopwatch()`
+    pub fn stopwatch_cycles(&self) -> u32 {
+        let high = self.timer_b.read();
+        let low = self.timer_value(Timer::A);
+        let value = match self.timer_b.read() {
+            // timer A underflowed during the read
+            high_again if high_again != high => ((high_again as u32) << 16) | 0xffff,
+            _ => ((high as u32) << 16) | low as u32,
+        };
+        u32::MAX - value
+    }
+
+    /// Select the mains frequency driving the time-of-day clock
+    pub fn set_tod_frequency(&self, frequency: TodFrequency) {
+        unsafe {
+            self.control_a.modify(|v| match frequency {
+                TodFrequency::Hz50 => v | TimerControl::TODIN,
+                TodFrequency::Hz60 => v & !TimerControl::TODIN,
+            });
+        }
+    }
+
+    /// Mains frequency driving the time-of-day clock
+    pub fn get_tod_frequency(&self) -> TodFrequency {
+        match self.control_a.read().contains(TimerControl::TODIN) {
+            true => TodFrequency::Hz50,
+            false => TodFrequency::Hz60,
+        }
+    }
+
+    /// Set and start the time-of-day clock
+    pub fn set_tod_time(&self, time: TodTime) {
+        unsafe { self.control_b.modify(|v| v & !TimerControl::ALARM) };
+        self.time_of_day.write_time(time);
+    }
+
+    /// Read the time-of-day clock
+    pub fn tod_time(&self) -> TodTime {
+        self.time_of_day.read_time()
+    }
+
+    /// Set the time-of-day alarm
+    ///
+    /// The `TOD_ALARM` interrupt is raised when the clock reaches `time`; see
+    /// `set_alarm_interrupt()`. The alarm registers are write-only.
+    ///
+    /// # Examples
+    /// ~~~
+    /// let cia = &c64::cia1().control;
+    /// cia.set_tod_time(TodTime::MIDNIGHT);
+    /// cia.set_alarm(TodTime::new(12, 0, 5, 0, false).unwrap());
+    /// irq::set_handler(irq::Interrupt::Cia1TodAlarm, || {
+    ///     // five seconds have passed
+    /// });
+    /// ~~~
+    pub fn set_alarm(&self, time: TodTime) {
+        unsafe { self.control_b.modify(|v| v | TimerControl::ALARM) };
+        self.time_of_day.write_time(time);
+        unsafe { self.control_b.modify(|v| v & !TimerControl::ALARM) };
+    }
+
+    /// Raise an interrupt when the time-of-day alarm fires, or stop doing so
+    pub fn set_alarm_interrupt(&self, enabled: bool) {
+        let flags = match enabled {
+            true => InterruptControl::SET_CLEAR | InterruptControl::TOD_ALARM,
+            false => InterruptControl::TOD_ALARM,
+        };
+        unsafe { self.interrupt.write(flags) };
+    }
+}
+
 #[repr(C, packed)]
 /// Registers for the MOS Technology Complex Interface Adapter 6526
 ///
@@ -218,6 +621,17 @@ pub struct MOSComplexInterfaceAdapter6526<
     pub control: Mos6526ControlBlock,
 }
 
+/// First CIA (keyboard and control ports)
+pub type MOSComplexInterfaceAdapter6526_1 =
+    MOSComplexInterfaceAdapter6526<CIA1PortA, CIA1PortB, CIA1DirectionA, CIA1DirectionB>;
+
+/// Second CIA (serial bus, user port and VIC bank)
+pub type MOSComplexInterfaceAdapter6526_2 =
+    MOSComplexInterfaceAdapter6526<CIA2PortA, CIA2PortB, CIA2DirectionA, CIA2DirectionB>;
+
+const_assert!(size_of::<MOSComplexInterfaceAdapter6526_1>() == 16);
+const_assert!(size_of::<MOSComplexInterfaceAdapter6526_2>() == 16);
+
 // === CIA1 ================================
 
 /// Keyboard or Joystick #2
@@ -308,8 +722,8 @@ impl From<CIA1PortB> for u8 {
 pub struct CIA1DirectionA(u8);
 
 impl CIA1DirectionA {
-    /// For Column input
-    pub const KEYBOARD: Self = Self(0b0000_0000);
+    /// Column select outputs for keyboard scanning
+    pub const KEYBOARD: Self = Self(0b1111_1111);
     /// For Joystick #2 input
     pub const JOYSTICK: Self = Self(0b0001_1111);
 }
@@ -338,8 +752,8 @@ impl From<CIA1DirectionA> for u8 {
 pub struct CIA1DirectionB(u8);
 
 impl CIA1DirectionB {
-    /// For Row input
-    pub const KEYBOARD: Self = Self(0b1111_1111);
+    /// Row inputs for keyboard scanning
+    pub const KEYBOARD: Self = Self(0b0000_0000);
     /// For Joystick #1 input
     pub const JOYSTICK: Self = Self(0b0001_1111);
 }
@@ -582,7 +996,11 @@ impl CIA2PortA {
 
 impl const Default for CIA2PortA {
     fn default() -> Self {
-        Self::DATA_OUT | Self::CLOCK_OUT | Self::ATN_OUT | Self::PA2 | Self::VIC_BANK_0
+        Self::DATA_OUT
+            .union(Self::CLOCK_OUT)
+            .union(Self::ATN_OUT)
+            .union(Self::PA2)
+            .union(Self::VIC_BANK_0)
     }
 }
 
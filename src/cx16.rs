@@ -44,6 +44,9 @@
 //! compatibility with the Commodore family of computers. It was initiated by David "the 8 Bit Guy" Murray,
 //! who wanted an accessible and affordable platform with late-80s sound and graphics.
 
+use crate::bus::RW;
+use crate::interrupt;
+use crate::peripheral::Peripheral;
 use crate::vera::VersatileEmbeddedRetroAdapter;
 
 pub const CH_COLOR_SWAP: u8 = 1;
@@ -124,34 +127,33 @@ pub const JOY_FIRE2_MASK: u8 = 64;
 pub const MOUSE_BTN_MIDDLE: u8 = 2;
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
 pub struct VersatileInterfaceAdapter65C22 {
     /// Port B `prb`
-    pub port_b: u8,
+    pub port_b: RW<u8>,
     /// Port A `pra`
-    pub port_a: u8,
+    pub port_a: RW<u8>,
     /// Data direction B `ddrb`
-    pub data_direction_b: u8,
+    pub data_direction_b: RW<u8>,
     /// Data direction A `ddra`
-    pub data_direction_a: u8,
+    pub data_direction_a: RW<u8>,
     /// Timer 1 `t1`
-    pub timer1: u16,
+    pub timer1: RW<u16>,
     /// Timer 1 latch `t1l`
-    pub timer1_latch: u16,
+    pub timer1_latch: RW<u16>,
     /// Timer 2 `t2`
-    pub timer2: u16,
+    pub timer2: RW<u16>,
     /// Shift `sr`
-    pub shift: u8,
+    pub shift: RW<u8>,
     /// Auxiliary control `acr`
-    pub auxiliary_control: u8,
+    pub auxiliary_control: RW<u8>,
     /// Peripheral control `pcr`
-    pub peripheral_control: u8,
+    pub peripheral_control: RW<u8>,
     /// Interrupt flag `ifr`
-    pub irq_flag: u8,
+    pub irq_flag: RW<u8>,
     /// Interrupt enable `ier`
-    pub irq_enable: u8,
+    pub irq_enable: RW<u8>,
     /// Port A w/o handshake `pra2`
-    pub port_a_no_handshape: u8,
+    pub port_a_no_handshape: RW<u8>,
 }
 
 #[repr(C)]
@@ -202,3 +204,48 @@ pub const YM2151: *mut u8 = (0x9f40) as _;
 pub const EMULATOR: *const Emulator = (0x9fb0) as _;
 
 pub const BANK_RAM: *mut u8 = (0xa000) as _;
+
+/// Set once `Peripherals` have been taken
+static mut PERIPHERALS_TAKEN: bool = false;
+
+/// Owned handles to the Commander X16 chips
+///
+/// # Examples
+/// ~~~
+/// let mut peripherals = cx16::Peripherals::take().unwrap();
+/// unsafe { peripherals.vera.irq_raster.write(100) };
+/// unsafe { peripherals.via1.data_direction_a.write(0xff) };
+/// ~~~
+pub struct Peripherals {
+    /// First Versatile Interface Adapter (0x9f00)
+    pub via1: Peripheral<VersatileInterfaceAdapter65C22, 0x9f00>,
+    /// Second Versatile Interface Adapter (0x9f10)
+    pub via2: Peripheral<VersatileInterfaceAdapter65C22, 0x9f10>,
+    /// Versatile Embedded Retro Adapter (0x9f20)
+    pub vera: Peripheral<VersatileEmbeddedRetroAdapter, 0x9f20>,
+}
+
+impl Peripherals {
+    /// Take all peripherals; returns `None` if already taken
+    pub fn take() -> Option<Self> {
+        interrupt::free(|| unsafe {
+            match PERIPHERALS_TAKEN {
+                true => None,
+                false => Some(Self::steal()),
+            }
+        })
+    }
+
+    /// Take all peripherals, regardless of whether they have already been taken
+    ///
+    /// # Safety
+    /// Unsafe as this may create multiple handles to the same chip
+    pub unsafe fn steal() -> Self {
+        PERIPHERALS_TAKEN = true;
+        Self {
+            via1: Peripheral::new(),
+            via2: Peripheral::new(),
+            vera: Peripheral::new(),
+        }
+    }
+}
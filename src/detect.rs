@@ -0,0 +1,173 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Detection of the video standard and system clock
+//!
+//! CIA timer rates, raster line counts and SID frequencies depend on the video
+//! standard of the machine:
+//!
+//! | Standard  | VIC-II    | Raster lines | Cycles per line | Clock (Hz) |
+//! |-----------|-----------|--------------|-----------------|------------|
+//! | `Pal`     | 6569      | 312          | 63              | 985 248    |
+//! | `Ntsc`    | 6567R8    | 263          | 65              | 1 022 727  |
+//! | `OldNtsc` | 6567R56A  | 262          | 64              | 1 022 727  |
+//! | `Drean`   | 6572      | 312          | 65              | 1 023 440  |
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::{detect, sid};
+//!
+//! let machine = detect::c64();
+//! let rs232 = c64::rs232::Config::new(c64::rs232::Baud::B1200).with_clock(machine.clock_hz);
+//! let notes = sid::note_table(machine.clock_hz);
+//! ~~~
+
+/// Video standard of the machine
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VideoStandard {
+    /// PAL-B, 50 Hz
+    Pal,
+    /// NTSC-M, 60 Hz
+    Ntsc,
+    /// NTSC-M of early C64s with 262 raster lines
+    OldNtsc,
+    /// PAL-N of the Argentinian Drean C64, 50 Hz
+    Drean,
+}
+
+impl VideoStandard {
+    /// System clock in Hz driving the CPU, CIA timers and SID
+    pub const fn clock_hz(&self) -> u32 {
+        match self {
+            Self::Pal => 985_248,
+            Self::Ntsc | Self::OldNtsc => 1_022_727,
+            Self::Drean => 1_023_440,
+        }
+    }
+
+    /// Raster lines per frame
+    pub const fn raster_lines(&self) -> u16 {
+        match self {
+            Self::Pal | Self::Drean => 312,
+            Self::Ntsc => 263,
+            Self::OldNtsc => 262,
+        }
+    }
+
+    /// Clock cycles per raster line
+    pub const fn cycles_per_line(&self) -> u8 {
+        match self {
+            Self::Pal => 63,
+            Self::Ntsc | Self::Drean => 65,
+            Self::OldNtsc => 64,
+        }
+    }
+
+    /// Clock cycles per frame
+    pub const fn cycles_per_frame(&self) -> u32 {
+        self.raster_lines() as u32 * self.cycles_per_line() as u32
+    }
+
+    /// True for the 50 Hz standards
+    pub const fn is_pal(&self) -> bool {
+        matches!(self, Self::Pal | Self::Drean)
+    }
+
+    /// Standard with the number of cycles per frame closest to `cycles`
+    pub const fn from_cycles_per_frame(cycles: u32) -> Self {
+        const STANDARDS: [VideoStandard; 4] = [
+            VideoStandard::OldNtsc,
+            VideoStandard::Ntsc,
+            VideoStandard::Pal,
+            VideoStandard::Drean,
+        ];
+        // midpoints between the standards ordered by cycles per frame
+        let mut i = 0;
+        while i < 3 {
+            let midpoint =
+                (STANDARDS[i].cycles_per_frame() + STANDARDS[i + 1].cycles_per_frame()) / 2;
+            if cycles < midpoint {
+                return STANDARDS[i];
+            }
+            i += 1;
+        }
+        STANDARDS[3]
+    }
+}
+
+/// System clock of the Commander X16 in Hz
+pub const CX16_CLOCK_HZ: u32 = 8_000_000;
+
+/// Detected video standard and system clock
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Machine {
+    pub standard: VideoStandard,
+    /// System clock in Hz driving the CIA or VIA timers and SID
+    pub clock_hz: u32,
+}
+
+impl Machine {
+    /// Machine with the clock of `standard`
+    pub const fn new(standard: VideoStandard) -> Self {
+        Self {
+            standard,
+            clock_hz: standard.clock_hz(),
+        }
+    }
+}
+
+/// Detect the C64 model by timing a frame
+///
+/// Counts the clock cycles between two passes of the beam over the same
+/// raster line with CIA1 timer B, with interrupts disabled. This takes up to
+/// three frames and tells all four VIC-II models apart, unlike the number of
+/// raster lines alone.
+#[cfg(feature = "c64")]
+pub fn c64() -> Machine {
+    use crate::c64::{cia1_control, vic2};
+    use crate::cia::{Timer, TimerMode};
+    const LINE: u16 = 100;
+    let wait_for_line = || {
+        while vic2().get_raster_line() == LINE {}
+        while vic2().get_raster_line() != LINE {}
+    };
+    let cycles = crate::interrupt::free(|| {
+        let cia = cia1_control();
+        wait_for_line();
+        cia.start_timer(Timer::B, u16::MAX, TimerMode::OneShot);
+        wait_for_line();
+        let remaining = cia.timer_value(Timer::B);
+        cia.stop_timer(Timer::B);
+        u16::MAX - remaining
+    });
+    Machine::new(VideoStandard::from_cycles_per_frame(cycles as u32))
+}
+
+/// Read the video standard selected in the VIC-IV `palntsc` register
+#[cfg(feature = "mega65")]
+pub fn mega65() -> Machine {
+    match crate::mega65::vic4().palntsc.read().get_ntsc() {
+        true => Machine::new(VideoStandard::Ntsc),
+        false => Machine::new(VideoStandard::Pal),
+    }
+}
+
+/// Commander X16, which always has NTSC timing at 60 Hz and an 8 MHz clock
+#[cfg(feature = "cx16")]
+pub const fn cx16() -> Machine {
+    Machine {
+        standard: VideoStandard::Ntsc,
+        clock_hz: CX16_CLOCK_HZ,
+    }
+}
@@ -0,0 +1,77 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Control of the 65xx interrupt disable flag
+//!
+//! These functions are common to all 65xx machines. On any other
+//! architecture, _e.g._ when running on the host, they do nothing.
+
+/// Interrupt disable bit (`I`) of the processor status register
+#[cfg(target_arch = "mos")]
+const INTERRUPT_DISABLE: u8 = 0b0000_0100;
+
+/// Disable maskable interrupts (`sei`) and return `true` if they were enabled
+#[inline(always)]
+pub fn disable() -> bool {
+    #[cfg(target_arch = "mos")]
+    {
+        let status: u8;
+        unsafe {
+            core::arch::asm!("php", "pla", "sei", out("a") status);
+        }
+        status & INTERRUPT_DISABLE == 0
+    }
+    #[cfg(not(target_arch = "mos"))]
+    false
+}
+
+/// Enable maskable interrupts (`cli`)
+///
+/// # Safety
+/// Unsafe as interrupt handlers may run immediately and break critical sections
+#[inline(always)]
+pub unsafe fn enable() {
+    #[cfg(target_arch = "mos")]
+    core::arch::asm!("cli");
+}
+
+/// Re-enable interrupts if `was_enabled` is true, typically the return value of `disable()`
+///
+/// # Safety
+/// Unsafe as interrupt handlers may run immediately and break critical sections
+#[inline(always)]
+pub unsafe fn restore(was_enabled: bool) {
+    if was_enabled {
+        enable();
+    }
+}
+
+/// Execute closure `f` with interrupts disabled
+///
+/// The previous interrupt state is restored afterwards so calls can be nested.
+///
+/// # Examples
+/// ~~~
+/// let value = interrupt::free(|| c64::vic2().raster_counter.read());
+/// ~~~
+#[inline(always)]
+pub fn free<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let was_enabled = disable();
+    let result = f();
+    unsafe { restore(was_enabled) };
+    result
+}
@@ -0,0 +1,160 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Joysticks in control port 1 and 2
+//!
+//! Both control ports are read through the first CIA: port 1 shares port B with the
+//! keyboard rows, and port 2 shares port A with the keyboard columns. A key held down
+//! in a selected column therefore looks like joystick 1 movement, _e.g._ SPACE reads
+//! as fire. `read()` deselects all keyboard columns while reading, which removes this
+//! crosstalk. The same CIA is found at 0xdc00 on both the C64 and the MEGA65.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::joystick::{Joystick, Port};
+//!
+//! let mut joystick = Joystick::new(Port::Two).with_autofire(8);
+//! loop {
+//!     joystick.update(c64::cia1()); // once per frame
+//!     let (dx, dy) = joystick.direction();
+//!     if joystick.autofire() {
+//!         // fire pressed, or held for 8 frames
+//!     }
+//! }
+//! ~~~
+
+use crate::cia::{CIA1PortA, GameController, JoystickPosition, MOSComplexInterfaceAdapter6526_1};
+
+/// Control port
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Port {
+    /// Control port 1 (CIA1 port B)
+    One,
+    /// Control port 2 (CIA1 port A)
+    Two,
+}
+
+/// Read active joystick bits of `port`, without keyboard crosstalk
+///
+/// Set bits correspond to active directions and fire, _i.e._ the complement of the
+/// raw port value.
+pub fn read(cia: &MOSComplexInterfaceAdapter6526_1, port: Port) -> GameController {
+    let port_a = cia.port_a.read();
+    unsafe { cia.port_a.write(CIA1PortA::from(0xff)) };
+    let raw = match port {
+        Port::One => GameController::from(cia.port_b.read()),
+        Port::Two => GameController::from(cia.port_a.read()),
+    };
+    unsafe { cia.port_a.write(port_a) };
+    raw.complement()
+}
+
+/// Joystick with fire button edge detection and autofire
+pub struct Joystick {
+    port: Port,
+    /// Active bits of the latest update
+    state: GameController,
+    /// Active bits of the update before
+    previous: GameController,
+    /// Updates between repeated fire events while fire is held; zero disables autofire
+    autofire_rate: u8,
+    /// Updates since the last fire event
+    autofire_count: u8,
+}
+
+impl Joystick {
+    /// New joystick in control `port`
+    pub const fn new(port: Port) -> Self {
+        Self {
+            port,
+            state: GameController::empty(),
+            previous: GameController::empty(),
+            autofire_rate: 0,
+            autofire_count: 0,
+        }
+    }
+
+    /// Repeat fire events every `updates` while fire is held; zero disables autofire
+    pub const fn with_autofire(mut self, updates: u8) -> Self {
+        self.autofire_rate = updates;
+        self
+    }
+
+    /// Control port of the joystick
+    pub const fn port(&self) -> Port {
+        self.port
+    }
+
+    /// Read the control port; call at a steady rate, _e.g._ once per frame
+    pub fn update(&mut self, cia: &MOSComplexInterfaceAdapter6526_1) {
+        self.update_with(read(cia, self.port));
+    }
+
+    /// Update from already read active bits, _e.g._ from `read()`
+    pub fn update_with(&mut self, state: GameController) {
+        self.previous = self.state;
+        self.state = state;
+        self.autofire_count = match self.fire_pressed() || !self.is_fire_down() {
+            true => 0,
+            false if self.autofire_count + 1 >= self.autofire_rate => 0,
+            false => self.autofire_count + 1,
+        };
+    }
+
+    /// Active directions and fire of the latest update
+    pub const fn state(&self) -> GameController {
+        self.state
+    }
+
+    /// Position of the latest update
+    pub const fn position(&self) -> JoystickPosition {
+        // `JoystickPosition::new()` expects the raw, active low value
+        JoystickPosition::new(self.state.complement())
+    }
+
+    /// Horizontal and vertical direction as -1, 0, or 1 (right and down are positive)
+    ///
+    /// Opposite directions cancel out.
+    pub const fn direction(&self) -> (i8, i8) {
+        let state = self.state;
+        let dx = state.contains(GameController::RIGHT) as i8
+            - state.contains(GameController::LEFT) as i8;
+        let dy =
+            state.contains(GameController::DOWN) as i8 - state.contains(GameController::UP) as i8;
+        (dx, dy)
+    }
+
+    /// True while fire is held down
+    pub const fn is_fire_down(&self) -> bool {
+        self.state.contains(GameController::FIRE)
+    }
+
+    /// True if fire was pressed in the latest update
+    pub const fn fire_pressed(&self) -> bool {
+        self.state.contains(GameController::FIRE) && !self.previous.contains(GameController::FIRE)
+    }
+
+    /// True if fire was released in the latest update
+    pub const fn fire_released(&self) -> bool {
+        !self.state.contains(GameController::FIRE) && self.previous.contains(GameController::FIRE)
+    }
+
+    /// True if fire was pressed, or held down for a multiple of the autofire rate
+    pub const fn autofire(&self) -> bool {
+        match self.autofire_rate {
+            0 => self.fire_pressed(),
+            _ => self.is_fire_down() && self.autofire_count == 0,
+        }
+    }
+}
@@ -31,8 +31,9 @@
 //! Use bitflags to control hardware behaviour, _e.g._ where the VIC-II chip accesses
 //! screen memory and character sets:
 //! ~~~
-//! let bank = vic2::ScreenBank::AT_2C00.bits() | vic2::ScreenBank::AT_2000.bits();
-//! c64::vic2().screen_and_charset_bank.write(bank);
+//! c64::vic2().screen_and_charset_bank.modify(|r| {
+//!     r.screen(vic2::ScreenBank::AT_2C00).charset(vic2::CharsetBank::AT_2000)
+//! });
 //! ~~~
 //!
 //! Convenience functions to perform hardware-specific tasks, _e.g._ generate random numbers
@@ -43,28 +44,44 @@
 //! ~~~
 
 #![no_std]
-#![feature(const_option)]
+#![cfg_attr(not(version("1.83")), feature(const_option))]
 #![allow(incomplete_features)]
 #![feature(generic_const_exprs)]
-#![feature(error_in_core)]
-#![feature(iter_advance_by)]
-#![feature(iter_next_chunk)]
+#![cfg_attr(not(version("1.81")), feature(error_in_core))]
+#![cfg_attr(feature = "mega65", feature(iter_advance_by, iter_next_chunk))]
 #![feature(cfg_version)]
 #![allow(clippy::bad_bit_mask)]
 #![feature(const_trait_impl)]
+#![cfg_attr(version("1.91"), feature(const_default, const_convert))]
+#![cfg_attr(not(target_arch = "mos"), feature(thread_local))]
 extern crate alloc;
 
+#[cfg(feature = "vic2")]
+pub mod bitmap;
+pub mod bus;
 #[cfg(feature = "c64")]
 pub mod c64;
 pub mod cbm_kernal;
+#[cfg(feature = "vic2")]
+pub mod charset;
 #[cfg(feature = "cia")]
 pub mod cia;
 #[cfg(feature = "cx16")]
 pub mod cx16;
+pub mod detect;
+pub mod interrupt;
+#[cfg(feature = "cia")]
+pub mod joystick;
 #[cfg(feature = "mega65")]
 pub mod mega65;
+#[cfg(all(feature = "cia", feature = "sid"))]
+pub mod paddle;
+pub mod peripheral;
 #[cfg(feature = "petscii")]
 pub mod petscii;
+pub mod ring_buffer;
+#[cfg(feature = "cia")]
+pub mod serial_shift;
 #[cfg(feature = "sid")]
 pub mod sid;
 #[cfg(feature = "vera")]
@@ -159,7 +176,7 @@ pub fn repeat_element<T: Clone>(
     it: impl Iterator<Item = T>,
     cnt: usize,
 ) -> impl Iterator<Item = T> {
-    it.flat_map(move |n| core::iter::repeat(n).take(cnt))
+    it.flat_map(move |n| core::iter::repeat_n(n, cnt))
 }
 
 /// Returns constantly evaluated _scaled_ and _shifted_ sine table.
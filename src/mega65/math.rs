@@ -15,9 +15,9 @@
 //! Mathematical support functions
 
 use super::MATH_STATUS;
+use crate::bus::{RO, WO};
 use bitflags::bitflags;
 use num_integer::Integer;
-use volatile_register::{RO, WO};
 
 bitflags! {
     /// Control flags for hardware multiplier/divider
@@ -24,10 +24,13 @@
 //! Like the original C65, it also has a Commodore 64 mode with a level of compatibility
 //! similar to that of the Commodore 128 running in C64 mode
 
+use crate::bus::RW;
+use crate::cia::{MOSComplexInterfaceAdapter6526_1, MOSComplexInterfaceAdapter6526_2};
+use crate::interrupt;
+use crate::peripheral::Peripheral;
 use crate::petscii;
 use crate::sid::*;
 use crate::vic2::*;
-use volatile_register::RW;
 
 pub mod iomap;
 pub mod libc;
@@ -149,6 +152,9 @@ pub const DEFAULT_UPPERCASE_FONT: *mut u8 = (0x1000) as _;
 pub const DEFAULT_MIXEDCASE_FONT: *mut u8 = (0x1800) as _;
 pub const VICII: *const MOSVideoInterfaceControllerII = (0xd000) as _;
 
+/// Pointer to VIC-IV specific registers (0xd020)
+pub const VICIV: *const vic4::Vic4 = (0xd020) as _;
+
 /// Pointer to first sound interface device
 pub const SID0: *const MOSSoundInterfaceDevice = (0xd400) as _;
 /// Pointer to second sound interface device
@@ -161,11 +167,17 @@ pub const SID3: *const MOSSoundInterfaceDevice = (0xd460) as _;
 pub const COLOR_RAM: *mut u8 = (0xd800) as _;
 
 /// Math multiplication-division status flags
-pub const MATH_STATUS: *const volatile_register::RO<math::StatusFlags> = (0xd70f) as _;
+pub const MATH_STATUS: *const crate::bus::RO<math::StatusFlags> = (0xd70f) as _;
 
 /// Math Acceleration registers
 pub const MATH_ACCELERATOR: *const math::MathAccelerator = (0xd768) as _;
 
+/// Pointer to first complex interface adapter (0xdc00)
+pub const CIA1: *const MOSComplexInterfaceAdapter6526_1 = (0xdc00) as _;
+
+/// Pointer to second complex interface adapter (0xdd00)
+pub const CIA2: *const MOSComplexInterfaceAdapter6526_2 = (0xdd00) as _;
+
 pub enum VicBank {
     Region0000 = 0x11, // Bank 0
     Region4000 = 0x10, // Bank 1
@@ -178,6 +190,11 @@ pub const fn vic2() -> &'static MOSVideoInterfaceControllerII {
     unsafe { &*VICII }
 }
 
+/// Get reference to VIC-IV specific registers
+pub const fn vic4() -> &'static vic4::Vic4 {
+    unsafe { &*VICIV }
+}
+
 /// Get reference to first SID chip
 pub const fn sid0() -> &'static MOSSoundInterfaceDevice {
     unsafe { &*SID0 }
@@ -203,6 +220,78 @@ pub const fn math_accelerator() -> &'static math::MathAccelerator {
     unsafe { &*MATH_ACCELERATOR }
 }
 
+/// Get reference to CIA1 chip
+pub const fn cia1() -> &'static MOSComplexInterfaceAdapter6526_1 {
+    unsafe { &*CIA1 }
+}
+
+/// Get reference to CIA2 chip
+pub const fn cia2() -> &'static MOSComplexInterfaceAdapter6526_2 {
+    unsafe { &*CIA2 }
+}
+
+/// Set once `Peripherals` have been taken
+static mut PERIPHERALS_TAKEN: bool = false;
+
+/// Owned handles to the MEGA65 chips
+///
+/// # Examples
+/// ~~~
+/// let peripherals = mega65::Peripherals::take().unwrap();
+/// let (product, _, _) = peripherals.math.multiply_divide(7, 3);
+/// ~~~
+pub struct Peripherals {
+    /// VIC-II compatible video registers (0xd000)
+    pub vic2: Peripheral<MOSVideoInterfaceControllerII, 0xd000>,
+    /// VIC-IV extended video registers (0xd020)
+    pub vic4: Peripheral<vic4::Vic4, 0xd020>,
+    /// First sound interface device (0xd400)
+    pub sid0: Peripheral<MOSSoundInterfaceDevice, 0xd400>,
+    /// Second sound interface device (0xd420)
+    pub sid1: Peripheral<MOSSoundInterfaceDevice, 0xd420>,
+    /// Third sound interface device (0xd440)
+    pub sid2: Peripheral<MOSSoundInterfaceDevice, 0xd440>,
+    /// Fourth sound interface device (0xd460)
+    pub sid3: Peripheral<MOSSoundInterfaceDevice, 0xd460>,
+    /// Math accelerator (0xd768)
+    pub math: Peripheral<math::MathAccelerator, 0xd768>,
+    /// First complex interface adapter (0xdc00)
+    pub cia1: Peripheral<MOSComplexInterfaceAdapter6526_1, 0xdc00>,
+    /// Second complex interface adapter (0xdd00)
+    pub cia2: Peripheral<MOSComplexInterfaceAdapter6526_2, 0xdd00>,
+}
+
+impl Peripherals {
+    /// Take all peripherals; returns `None` if already taken
+    pub fn take() -> Option<Self> {
+        interrupt::free(|| unsafe {
+            match PERIPHERALS_TAKEN {
+                true => None,
+                false => Some(Self::steal()),
+            }
+        })
+    }
+
+    /// Take all peripherals, regardless of whether they have already been taken
+    ///
+    /// # Safety
+    /// Unsafe as this may create multiple handles to the same chip
+    pub unsafe fn steal() -> Self {
+        PERIPHERALS_TAKEN = true;
+        Self {
+            vic2: Peripheral::new(),
+            vic4: Peripheral::new(),
+            sid0: Peripheral::new(),
+            sid1: Peripheral::new(),
+            sid2: Peripheral::new(),
+            sid3: Peripheral::new(),
+            math: Peripheral::new(),
+            cia1: Peripheral::new(),
+            cia2: Peripheral::new(),
+        }
+    }
+}
+
 /// Control CPU clock speed
 #[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Default)]
@@ -219,20 +308,19 @@ pub enum CPUSpeed {
 impl CPUSpeed {
     /// Set CPU speed
     pub fn set(&self) {
-        const VICIV_CTRLB: *mut RW<u8> = 0xd031 as _;
-        const VICIV_CTRLC: *mut RW<u8> = 0xd054 as _;
+        const VICIV_CTRLB: *mut RW<vic4::Vic3Control> = 0xd031 as _;
         match self {
             Self::Slow => unsafe {
-                (*VICIV_CTRLB).modify(|m| m & 0b1011_1111);
-                (*VICIV_CTRLC).modify(|m| m & 0b1011_1111);
+                (*VICIV_CTRLB).modify(|m| m - vic4::Vic3Control::FAST);
+                vic4().ctrl_c.modify(|m| m - vic4::Vic4Control::VFAST);
             },
             Self::Medium => unsafe {
-                (*VICIV_CTRLB).modify(|m| m | 0b0100_0000);
-                (*VICIV_CTRLC).modify(|m| m & 0b1011_1111);
+                (*VICIV_CTRLB).modify(|m| m | vic4::Vic3Control::FAST);
+                vic4().ctrl_c.modify(|m| m - vic4::Vic4Control::VFAST);
             },
             Self::Fast => unsafe {
-                (*VICIV_CTRLB).modify(|m| m | 0b0100_0000);
-                (*VICIV_CTRLC).modify(|m| m | 0b0100_0000);
+                (*VICIV_CTRLB).modify(|m| m | vic4::Vic3Control::FAST);
+                vic4().ctrl_c.modify(|m| m | vic4::Vic4Control::VFAST);
             },
         }
     }
@@ -1,5 +1,7 @@
+use crate::bus::RW;
 use bitflags::bitflags;
-use volatile_register::RW;
+use core::mem::size_of;
+use static_assertions::const_assert;
 
 bitflags! {
     /// VIC-III control flags for `0xD031`
@@ -24,7 +26,7 @@ bitflags! {
 }
 
 bitflags! {
-    /// VIC-IV control flags for `0xD051`
+    /// VIC-IV control flags for `0xD054`
     pub struct Vic4Control: u8 {
         /// Alpha compositor enable
         const ALPHEN = 0b1000_0000;
@@ -45,6 +47,517 @@ bitflags! {
     }
 }
 
+/// Position bits 8-11 and four sprite enable bits (0xD049, 0xD04B, 0xD04D, 0xD04F)
+///
+/// The VIC-IV shares these registers between the most significant bits of a 12-bit
+/// position (lower nibble) and per-sprite enables (upper nibble).
+///
+/// # Examples
+/// ~~~
+/// mega65::vic4().topborder_pos.write(0x58);
+/// mega65::vic4().sprite_bitplane_modify_enable.modify(|r| r.position_msb(0));
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct PositionMsb(u8);
+
+impl PositionMsb {
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set position bits 8-11
+    pub const fn position_msb(self, msb: u8) -> Self {
+        Self((self.0 & 0xf0) | (msb & 0x0f))
+    }
+
+    /// Set the four sprite enable bits
+    pub const fn sprites(self, sprites: u8) -> Self {
+        Self((self.0 & 0x0f) | (sprites << 4))
+    }
+
+    /// Position bits 8-11
+    pub const fn get_position_msb(&self) -> u8 {
+        self.0 & 0x0f
+    }
+
+    /// The four sprite enable bits
+    pub const fn get_sprites(&self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+/// Fine raster MSB and raster compare source (`0xD053`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct FineRaster(u8);
+
+impl FineRaster {
+    const SHDEMU: u8 = 0b0100_0000;
+    const FNRST: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Enable simulated shadow-mask, `SHDEMU` (`PALEMU` must also be enabled)
+    pub const fn shadow_mask(self, enable: bool) -> Self {
+        Self((self.0 & !Self::SHDEMU) | if enable { Self::SHDEMU } else { 0 })
+    }
+
+    /// Raster compare source, `FNRST` (`false` = VIC-IV fine raster, `true` = VIC-II raster)
+    pub const fn vic2_raster_compare(self, enable: bool) -> Self {
+        Self((self.0 & !Self::FNRST) | if enable { Self::FNRST } else { 0 })
+    }
+
+    /// Physical raster position bits 8-10 (read-only)
+    pub const fn get_raster_msb(&self) -> u8 {
+        self.0 & 0b0000_0111
+    }
+
+    /// True if simulated shadow-mask is enabled
+    pub const fn get_shadow_mask(&self) -> bool {
+        self.0 & Self::SHDEMU != 0
+    }
+
+    /// True if raster compare is against the VIC-II raster
+    pub const fn get_vic2_raster_compare(&self) -> bool {
+        self.0 & Self::FNRST != 0
+    }
+}
+
+/// Side border width MSB, raster delay, and hot registers (`0xD05D`)
+///
+/// # Examples
+/// ~~~
+/// mega65::vic4().hotreg.modify(|r| r.hot_registers(false));
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct HotRegisters(u8);
+
+impl HotRegisters {
+    const SDBDRWD_MSB_MASK: u8 = 0b0011_1111;
+    const RSTDELEN: u8 = 0b0100_0000;
+    const HOTREG: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set side border width bits 8-13
+    pub const fn side_border_msb(self, msb: u8) -> Self {
+        Self((self.0 & !Self::SDBDRWD_MSB_MASK) | (msb & Self::SDBDRWD_MSB_MASK))
+    }
+
+    /// Delay raster counter and interrupts by one line to match output pipeline latency
+    pub const fn raster_delay(self, enable: bool) -> Self {
+        Self((self.0 & !Self::RSTDELEN) | if enable { Self::RSTDELEN } else { 0 })
+    }
+
+    /// Enable VIC-II hot registers
+    ///
+    /// When enabled, touching many VIC-II registers causes the VIC-IV to
+    /// recalculate display parameters, such as border positions and sizes.
+    pub const fn hot_registers(self, enable: bool) -> Self {
+        Self((self.0 & !Self::HOTREG) | if enable { Self::HOTREG } else { 0 })
+    }
+
+    /// Side border width bits 8-13
+    pub const fn get_side_border_msb(&self) -> u8 {
+        self.0 & Self::SDBDRWD_MSB_MASK
+    }
+
+    /// True if raster delay is enabled
+    pub const fn get_raster_delay(&self) -> bool {
+        self.0 & Self::RSTDELEN != 0
+    }
+
+    /// True if VIC-II hot registers are enabled
+    pub const fn get_hot_registers(&self) -> bool {
+        self.0 & Self::HOTREG != 0
+    }
+}
+
+/// Screen RAM megabyte, character count MSB, and `EXGLYPH` (`0xD063`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct ScreenPointerMsb(u8);
+
+impl ScreenPointerMsb {
+    const EXGLYPH: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set screen RAM base address bits 24-27
+    pub const fn screen_megabyte(self, megabyte: u8) -> Self {
+        Self((self.0 & 0xf0) | (megabyte & 0x0f))
+    }
+
+    /// Set bits 8-9 of number of characters to display per row
+    pub const fn chrcount_msb(self, msb: u8) -> Self {
+        Self((self.0 & 0b1100_1111) | ((msb & 0b11) << 4))
+    }
+
+    /// Source full-colour character data from expansion RAM
+    pub const fn expansion_glyphs(self, enable: bool) -> Self {
+        Self((self.0 & !Self::EXGLYPH) | if enable { Self::EXGLYPH } else { 0 })
+    }
+
+    /// Screen RAM base address bits 24-27
+    pub const fn get_screen_megabyte(&self) -> u8 {
+        self.0 & 0x0f
+    }
+
+    /// Bits 8-9 of number of characters to display per row
+    pub const fn get_chrcount_msb(&self) -> u8 {
+        (self.0 >> 4) & 0b11
+    }
+
+    /// True if full-colour character data is sourced from expansion RAM
+    pub const fn get_expansion_glyphs(&self) -> bool {
+        self.0 & Self::EXGLYPH != 0
+    }
+}
+
+/// Sprite pointer bank and 16-bit sprite pointer mode (`0xD06E`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct SpritePointerBank(u8);
+
+impl SpritePointerBank {
+    const SPRPTR16: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set sprite pointer address bits 16-22
+    pub const fn bank(self, bank: u8) -> Self {
+        Self((self.0 & Self::SPRPTR16) | (bank & !Self::SPRPTR16))
+    }
+
+    /// Enable 16-bit sprite pointers, allowing sprites on any 64 byte boundary in chip RAM
+    pub const fn pointer16(self, enable: bool) -> Self {
+        Self((self.0 & !Self::SPRPTR16) | if enable { Self::SPRPTR16 } else { 0 })
+    }
+
+    /// Sprite pointer address bits 16-22
+    pub const fn get_bank(&self) -> u8 {
+        self.0 & !Self::SPRPTR16
+    }
+
+    /// True if 16-bit sprite pointers are enabled
+    pub const fn get_pointer16(&self) -> bool {
+        self.0 & Self::SPRPTR16 != 0
+    }
+}
+
+/// First raster line, VGA mode, and PAL/NTSC selection (`0xD06F`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct VideoTiming(u8);
+
+impl VideoTiming {
+    const RASLINE0_MASK: u8 = 0b0011_1111;
+    const VGAHDTV: u8 = 0b0100_0000;
+    const PALNTSC: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set first VIC-II raster line
+    pub const fn first_raster(self, line: u8) -> Self {
+        Self((self.0 & !Self::RASLINE0_MASK) | (line & Self::RASLINE0_MASK))
+    }
+
+    /// Select more VGA-compatible mode instead of HDMI/HDTV VIC-II cycle-exact frame timing
+    pub const fn vga(self, enable: bool) -> Self {
+        Self((self.0 & !Self::VGAHDTV) | if enable { Self::VGAHDTV } else { 0 })
+    }
+
+    /// NTSC emulation mode (max raster = 262)
+    pub const fn ntsc(self, enable: bool) -> Self {
+        Self((self.0 & !Self::PALNTSC) | if enable { Self::PALNTSC } else { 0 })
+    }
+
+    /// First VIC-II raster line
+    pub const fn get_first_raster(&self) -> u8 {
+        self.0 & Self::RASLINE0_MASK
+    }
+
+    /// True if VGA-compatible mode is selected
+    pub const fn get_vga(&self) -> bool {
+        self.0 & Self::VGAHDTV != 0
+    }
+
+    /// True if in NTSC mode
+    pub const fn get_ntsc(&self) -> bool {
+        self.0 & Self::PALNTSC != 0
+    }
+}
+
+/// Palette bank selection (`0xD070`)
+///
+/// Each field selects one of four palette banks.
+///
+/// # Examples
+/// ~~~
+/// mega65::vic4().ctrl2.modify(|r| r.mapped(1).text(1));
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct PaletteSelect(u8);
+
+impl PaletteSelect {
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    const fn set(self, shift: u8, bank: u8) -> Self {
+        Self((self.0 & !(0b11 << shift)) | ((bank & 0b11) << shift))
+    }
+
+    const fn get(&self, shift: u8) -> u8 {
+        (self.0 >> shift) & 0b11
+    }
+
+    /// Set alternate bitmap/text palette bank, `ABTPALSEL`
+    pub const fn alternate_text(self, bank: u8) -> Self {
+        self.set(0, bank)
+    }
+
+    /// Set sprite palette bank, `SPRPALSEL`
+    pub const fn sprites(self, bank: u8) -> Self {
+        self.set(2, bank)
+    }
+
+    /// Set bitmap/text palette bank, `BTPALSEL`
+    pub const fn text(self, bank: u8) -> Self {
+        self.set(4, bank)
+    }
+
+    /// Set palette bank mapped at $D100-$D3FF, `MAPEDPAL`
+    pub const fn mapped(self, bank: u8) -> Self {
+        self.set(6, bank)
+    }
+
+    /// Alternate bitmap/text palette bank
+    pub const fn get_alternate_text(&self) -> u8 {
+        self.get(0)
+    }
+
+    /// Sprite palette bank
+    pub const fn get_sprites(&self) -> u8 {
+        self.get(2)
+    }
+
+    /// Bitmap/text palette bank
+    pub const fn get_text(&self) -> u8 {
+        self.get(4)
+    }
+
+    /// Palette bank mapped at $D100-$D3FF
+    pub const fn get_mapped(&self) -> u8 {
+        self.get(6)
+    }
+}
+
+/// Alpha compositor delay and physical rasters per VIC-II raster (`0xD073`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct AlphaDelay(u8);
+
+impl AlphaDelay {
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set alpha delay for compositor (0-15)
+    pub const fn delay(self, delay: u8) -> Self {
+        Self((self.0 & 0xf0) | (delay & 0x0f))
+    }
+
+    /// Set physical rasters per VIC-II raster (0-15)
+    pub const fn raster_height(self, height: u8) -> Self {
+        Self((self.0 & 0x0f) | (height << 4))
+    }
+
+    /// Alpha delay for compositor
+    pub const fn get_delay(&self) -> u8 {
+        self.0 & 0x0f
+    }
+
+    /// Physical rasters per VIC-II raster
+    pub const fn get_raster_height(&self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+/// Raster compare MSB and extra interrupt control (`0xD07A`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct RasterCompareMsb(u8);
+
+impl RasterCompareMsb {
+    const RASCMP_MSB_MASK: u8 = 0b0000_0111;
+    const SPTR: u8 = 0b0000_1000;
+    const EXTIRQS: u8 = 0b0100_0000;
+    const FNRST: u8 = 0b1000_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set raster compare bits 8-10
+    pub const fn compare_msb(self, msb: u8) -> Self {
+        Self((self.0 & !Self::RASCMP_MSB_MASK) | (msb & Self::RASCMP_MSB_MASK))
+    }
+
+    /// Continuously monitor sprite pointer, to allow changing sprite data source while a sprite is being drawn
+    pub const fn monitor_sprite_pointer(self, enable: bool) -> Self {
+        Self((self.0 & !Self::SPTR) | if enable { Self::SPTR } else { 0 })
+    }
+
+    /// Enable additional IRQ sources, _e.g._ raster X position
+    pub const fn extra_irqs(self, enable: bool) -> Self {
+        Self((self.0 & !Self::EXTIRQS) | if enable { Self::EXTIRQS } else { 0 })
+    }
+
+    /// Raster compare is in physical rasters if set, or VIC-II raster if clear
+    pub const fn physical_raster(self, enable: bool) -> Self {
+        Self((self.0 & !Self::FNRST) | if enable { Self::FNRST } else { 0 })
+    }
+
+    /// Raster compare bits 8-10
+    pub const fn get_compare_msb(&self) -> u8 {
+        self.0 & Self::RASCMP_MSB_MASK
+    }
+
+    /// True if sprite pointers are continuously monitored
+    pub const fn get_monitor_sprite_pointer(&self) -> bool {
+        self.0 & Self::SPTR != 0
+    }
+
+    /// True if additional IRQ sources are enabled
+    pub const fn get_extra_irqs(&self) -> bool {
+        self.0 & Self::EXTIRQS != 0
+    }
+
+    /// True if raster compare is in physical rasters
+    pub const fn get_physical_raster(&self) -> bool {
+        self.0 & Self::FNRST != 0
+    }
+}
+
+/// Bitplane bank and sync polarity (`0xD07C`)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct SyncPolarity(u8);
+
+impl SyncPolarity {
+    const BITPBANK_MASK: u8 = 0b0000_0111;
+    const HSYNCP: u8 = 0b0001_0000;
+    const VSYNCP: u8 = 0b0010_0000;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set which 128KB bank bitplanes are fetched from
+    pub const fn bitplane_bank(self, bank: u8) -> Self {
+        Self((self.0 & !Self::BITPBANK_MASK) | (bank & Self::BITPBANK_MASK))
+    }
+
+    /// Set hsync polarity
+    pub const fn hsync(self, positive: bool) -> Self {
+        Self((self.0 & !Self::HSYNCP) | if positive { Self::HSYNCP } else { 0 })
+    }
+
+    /// Set vsync polarity
+    pub const fn vsync(self, positive: bool) -> Self {
+        Self((self.0 & !Self::VSYNCP) | if positive { Self::VSYNCP } else { 0 })
+    }
+
+    /// 128KB bank that bitplanes are fetched from
+    pub const fn get_bitplane_bank(&self) -> u8 {
+        self.0 & Self::BITPBANK_MASK
+    }
+
+    /// Hsync polarity
+    pub const fn get_hsync(&self) -> bool {
+        self.0 & Self::HSYNCP != 0
+    }
+
+    /// Vsync polarity
+    pub const fn get_vsync(&self) -> bool {
+        self.0 & Self::VSYNCP != 0
+    }
+}
+
 /// VIC-IV specific registers
 #[repr(C)]
 pub struct Vic4 {
@@ -62,8 +575,8 @@ pub struct Vic4 {
     pub sprite_multicolor0: RW<u8>,
     /// Offset 0x26
     pub sprite_multicolor1: RW<u8>,
-    /// Reserved (offset 0x27 to 0x2d)
-    pub _reserved1: [u8; 7],
+    /// Reserved (offset 0x27 to 0x2e)
+    pub _reserved1: [u8; 8],
     /// Write `0x47` then `0x53` to enable C65GS/VIC-IV IO registers (offset 0x2f)
     pub key: RW<u8>,
     /// Reserved (offset 0x30 to 0x47)
@@ -71,19 +584,19 @@ pub struct Vic4 {
     /// TBDRPOS (offset 0x48)
     pub topborder_pos: RW<u8>,
     /// Sprite bitplane-modify-mode enables, `SPRBPMEN` (offset 0x49)
-    pub sprite_bitplane_modify_enable: RW<u8>,
+    pub sprite_bitplane_modify_enable: RW<PositionMsb>,
     /// BBDRPOS (offset 0x4a)
     pub bottomborder_pos: RW<u8>,
     /// SPRBPMEN (offset 0x4b)
-    pub sprbpmen2: RW<u8>,
+    pub sprbpmen2: RW<PositionMsb>,
     /// Character generator horizontal position TEXTXPOS (offset 0x4c)
     pub textxpos: RW<u8>,
     /// SPRTILEN (offset 0x4d)
-    pub sprtilen: RW<u8>,
+    pub sprtilen: RW<PositionMsb>,
     /// Character generator vertical position TEXTYPOS (offset 0x4e)
     pub textypos: RW<u8>,
     /// SPRTILEN (offset 0x4f)
-    pub sprtilen2: RW<u8>,
+    pub sprtilen2: RW<PositionMsb>,
     /// XPOSLSB (offset 0x50)
     pub xpos_lsb: RW<u8>,
     /// NORRDEL (offset 0x51)
@@ -91,9 +604,9 @@ pub struct Vic4 {
     /// FNRASTERLSB (offset 0x52)
     pub fnraster_lsb: RW<u8>,
     /// VIC-IV Control register (offset 0x53)
-    pub ctrl1: RW<u8>,
+    pub ctrl1: RW<FineRaster>,
     /// VIC-IV Control register C (offset 0x54)
-    pub ctrl_c: RW<u8>,
+    pub ctrl_c: RW<Vic4Control>,
     /// Sprite extended height enable (one bit per sprite) (`SPRHGTEN`, offset 0x55)
     pub sprite_extended_height_enable: RW<u8>,
     /// Sprite extended height size (sprite pixels high) (`SPRHGHT`, offset 0x56)
@@ -109,7 +622,7 @@ pub struct Vic4 {
     /// SDBDRWDLSB (offset 0x5C)
     pub sdbdrwd_lsb: RW<u8>,
     /// HOTREG (offset 0x5D)
-    pub hotreg: RW<u8>,
+    pub hotreg: RW<HotRegisters>,
     /// Number of characters to display per row, LSB (offset: 0x5E)
     pub chrcount: RW<u8>,
     /// Sprite H640 X Super-MSBs (`SPRXSMSBS`, offset 0x5F)
@@ -121,7 +634,7 @@ pub struct Vic4 {
     /// Screen RAM precise base address (SCRNPTRBNK, offset 0x62)
     pub scrnptr_bnk: RW<u8>,
     /// EXGLYPH (offset 0x63)
-    pub exglyph: RW<u8>,
+    pub exglyph: RW<ScreenPointerMsb>,
     /// Colour RAM base address, `COLPTR` (offset 0x64-0x65)
     pub colptr_lsb: RW<u16>,
     /// Reserved (offset 0x66 to 0x67)
@@ -137,17 +650,17 @@ pub struct Vic4 {
     /// Sprite pointer address, `SPRPTRADR` (offset 0x6C - ox6D)
     pub sprite_ptr_address: RW<u16>,
     /// SPRPTR16EN (offset 0x6E)
-    pub sprptr16en: RW<u8>,
+    pub sprptr16en: RW<SpritePointerBank>,
     /// PALNTSC (offset 0x6F)
-    pub palntsc: RW<u8>,
-    /// VIC-IV control register 2 (offset 0x70)
-    pub ctrl2: RW<u8>,
+    pub palntsc: RW<VideoTiming>,
+    /// Palette bank selection (offset 0x70)
+    pub ctrl2: RW<PaletteSelect>,
     /// VIC-IV 16-colour bitplane enable flags (offset 0x71)
     pub bp16ens: RW<u8>,
     /// Sprite Y position adjustment (offset 0x72)
     pub spr_yadj: RW<u8>,
     /// Alpha delay and raster height (offset 0x73)
-    pub alphadelay: RW<u8>,
+    pub alphadelay: RW<AlphaDelay>,
     /// Sprite alpha-blend enable (offset 0x74)
     pub spr_enalpha: RW<u8>,
     /// Sprite alpha-blend value (offset 0x75)
@@ -161,13 +674,15 @@ pub struct Vic4 {
     /// Raster compare value (offset 0x79)
     pub rstcmp: RW<u8>,
     /// VIC-IV control register 3 (offset 0x7A)
-    pub ctrl3: RW<u8>,
+    pub ctrl3: RW<RasterCompareMsb>,
     /// Number of text rows to display (offset 0x7B)
     pub disp_rows: RW<u8>,
     /// hsync/vsync polarity (offset 0x7C)
-    pub debugc: RW<u8>,
+    pub debugc: RW<SyncPolarity>,
 }
 
+const_assert!(size_of::<Vic4>() == 0x7d - 0x20);
+
 #[allow(non_camel_case_types)]
 pub struct __vic4__bindgen_ty_7__bindgen_ty_1 {
     ///Display Address Translater (DAT) Bitplane 0 port (offset 0x40)
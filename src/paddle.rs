@@ -0,0 +1,185 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Paddles and the Commodore 1351 mouse
+//!
+//! Both devices are read through the SID potentiometer inputs. Only one control
+//! port is connected to the SID at a time, selected by bits 6-7 of CIA1 port A,
+//! and the SID samples the inputs every 512 cycles. After selecting a port,
+//! reading is therefore delayed using CIA1 timer B in one-shot mode, which is
+//! independent of the CPU speed. Interrupts are disabled meanwhile since the
+//! KERNAL keyboard scan also writes port A.
+//!
+//! # Examples
+//! ~~~
+//! use mos_hardware::joystick::Port;
+//! use mos_hardware::paddle::{self, Mouse};
+//!
+//! let [a, b, c, d] = paddle::read_all(c64::cia1(), c64::sid());
+//! if a.fire {
+//!     // ...
+//! }
+//!
+//! let mut mouse = Mouse::new(Port::One);
+//! loop {
+//!     let (dx, dy) = mouse.update(c64::cia1(), c64::sid()); // once per frame
+//! }
+//! ~~~
+
+use crate::cia::{CIA1PortA, GameController, MOSComplexInterfaceAdapter6526_1, Timer, TimerMode};
+use crate::interrupt;
+use crate::joystick::{self, Port};
+use crate::sid::MOSSoundInterfaceDevice;
+
+/// Cycles to wait after selecting a port, allowing the SID to complete a sample
+pub const SETTLE_CYCLES: u16 = 2 * 512;
+
+/// Port A bits 6-7 connecting a control port to the SID
+const SELECT_MASK: u8 = 0b1100_0000;
+
+/// Port A bits 6-7 for `port`
+const fn select_bits(port: Port) -> u8 {
+    match port {
+        Port::One => 0b0100_0000,
+        Port::Two => 0b1000_0000,
+    }
+}
+
+/// Read the SID potentiometers (x, y) of control `port`
+///
+/// Uses CIA1 timer B to wait for the SID. Port A is restored afterwards.
+pub fn read_pots(
+    cia: &MOSComplexInterfaceAdapter6526_1,
+    sid: &MOSSoundInterfaceDevice,
+    port: Port,
+) -> (u8, u8) {
+    interrupt::free(|| {
+        let port_a = cia.port_a.read();
+        let select = (u8::from(port_a) & !SELECT_MASK) | select_bits(port);
+        unsafe { cia.port_a.write(CIA1PortA::from(select)) };
+        cia.control
+            .start_timer(Timer::B, SETTLE_CYCLES, TimerMode::OneShot);
+        cia.control.wait_timer(Timer::B);
+        let pots = (sid.potentiometer_x.read(), sid.potentiometer_y.read());
+        unsafe { cia.port_a.write(port_a) };
+        pots
+    })
+}
+
+/// Paddle position and fire button
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Paddle {
+    /// Potentiometer value; 0-255
+    pub position: u8,
+    /// True if the fire button is pressed
+    pub fire: bool,
+}
+
+/// Read the two paddles in control `port`
+///
+/// The first paddle uses the x potentiometer and the joystick left line for fire;
+/// the second uses the y potentiometer and the right line.
+pub fn read_pair(
+    cia: &MOSComplexInterfaceAdapter6526_1,
+    sid: &MOSSoundInterfaceDevice,
+    port: Port,
+) -> [Paddle; 2] {
+    let (x, y) = read_pots(cia, sid, port);
+    let buttons = joystick::read(cia, port);
+    [
+        Paddle {
+            position: x,
+            fire: buttons.contains(GameController::LEFT),
+        },
+        Paddle {
+            position: y,
+            fire: buttons.contains(GameController::RIGHT),
+        },
+    ]
+}
+
+/// Read all four paddles; first the pair in port 1, then the pair in port 2
+pub fn read_all(
+    cia: &MOSComplexInterfaceAdapter6526_1,
+    sid: &MOSSoundInterfaceDevice,
+) -> [Paddle; 4] {
+    let [a, b] = read_pair(cia, sid, Port::One);
+    let [c, d] = read_pair(cia, sid, Port::Two);
+    [a, b, c, d]
+}
+
+/// Signed movement between two 1351 potentiometer readings
+///
+/// The mouse reports its position modulo 64 in bits 1-6; bit 0 is noise.
+/// Follows the driver in the 1351 user's manual.
+const fn mouse_delta(old: u8, new: u8) -> i8 {
+    let delta = new.wrapping_sub(old) & 0b0111_1111;
+    if delta < 0b0100_0000 {
+        (delta >> 1) as i8
+    } else {
+        match delta | 0b1100_0000 {
+            0xff => 0,
+            delta => (delta as i8) >> 1,
+        }
+    }
+}
+
+/// Commodore 1351 mouse in proportional mode
+pub struct Mouse {
+    port: Port,
+    /// Potentiometer readings from the previous update
+    pots: Option<(u8, u8)>,
+    buttons: GameController,
+}
+
+impl Mouse {
+    /// New mouse in control `port`
+    pub const fn new(port: Port) -> Self {
+        Self {
+            port,
+            pots: None,
+            buttons: GameController::empty(),
+        }
+    }
+
+    /// Read the mouse and return the movement since the last update
+    ///
+    /// Right and down are positive. Call at least once per frame so that fast
+    /// movements do not exceed the 6-bit range of the mouse. The first update
+    /// returns no movement.
+    pub fn update(
+        &mut self,
+        cia: &MOSComplexInterfaceAdapter6526_1,
+        sid: &MOSSoundInterfaceDevice,
+    ) -> (i8, i8) {
+        let (x, y) = read_pots(cia, sid, self.port);
+        self.buttons = joystick::read(cia, self.port);
+        let delta = match self.pots {
+            Some((old_x, old_y)) => (mouse_delta(old_x, x), -mouse_delta(old_y, y)),
+            None => (0, 0),
+        };
+        self.pots = Some((x, y));
+        delta
+    }
+
+    /// True if the left button was held at the last update
+    pub const fn left_button(&self) -> bool {
+        self.buttons.contains(GameController::FIRE)
+    }
+
+    /// True if the right button was held at the last update
+    pub const fn right_button(&self) -> bool {
+        self.buttons.contains(GameController::UP)
+    }
+}
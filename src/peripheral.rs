@@ -0,0 +1,77 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Owned handles to memory mapped chips
+//!
+//! Each machine module (`c64`, `mega65`, `cx16`) has a `Peripherals` struct
+//! that can be taken exactly once and which holds one `Peripheral` handle per chip.
+//! As there is only one handle per chip, `&mut` access to _e.g._ the `reset()`
+//! functions of the CIA chips is enforced by the type system.
+//!
+//! # Examples
+//! ~~~
+//! let mut peripherals = c64::Peripherals::take().unwrap();
+//! peripherals.cia1.reset();
+//! unsafe { peripherals.vic2.border_color.write(vic2::RED) };
+//! assert!(c64::Peripherals::take().is_none()); // already taken
+//! ~~~
+
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+/// Owned handle to register block `T` placed at `ADDRESS`
+///
+/// The handle is zero-sized and dereferences to the register block.
+pub struct Peripheral<T, const ADDRESS: u16> {
+    _marker: PhantomData<*const T>,
+}
+
+impl<T, const ADDRESS: u16> Peripheral<T, ADDRESS> {
+    /// Create handle
+    ///
+    /// # Safety
+    /// Unsafe as this allows multiple handles to the same chip.
+    /// Use the machine's `Peripherals::take()` instead.
+    pub const unsafe fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    /// Address of the register block
+    pub const fn address(&self) -> u16 {
+        ADDRESS
+    }
+
+    /// Pointer to the register block
+    pub const fn ptr() -> *const T {
+        ADDRESS as usize as *const T
+    }
+}
+
+impl<T, const ADDRESS: u16> Deref for Peripheral<T, ADDRESS> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        unsafe { &*Self::ptr() }
+    }
+}
+
+impl<T, const ADDRESS: u16> DerefMut for Peripheral<T, ADDRESS> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *(Self::ptr() as *mut T) }
+    }
+}
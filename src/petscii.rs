@@ -120,14 +120,22 @@ impl Petscii {
 
     /// Create from unicode character
     pub const fn from_char(letter: char) -> Petscii {
+        match Petscii::try_from_char(letter) {
+            Some(petscii) => petscii,
+            None => panic!("INVALID LETTER"),
+        }
+    }
+
+    /// Create from unicode character; `None` if there is no PETSCII equivalent
+    pub const fn try_from_char(letter: char) -> Option<Petscii> {
         let mut petscii = 0;
         while petscii < PETSCII_TO_CHAR_LOWERCASE.len() {
             if letter == PETSCII_TO_CHAR_LOWERCASE[petscii] {
-                return Petscii::from_byte(petscii as u8);
+                return Some(Petscii::from_byte(petscii as u8));
             }
             petscii += 1;
         }
-        panic!("INVALID LETTER");
+        None
     }
 
     /// Convert PETSCII to screen code
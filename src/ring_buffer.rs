@@ -0,0 +1,118 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Fixed size byte queue shared between an interrupt handler and the main program
+//!
+//! The buffer is safe for a single producer and a single consumer, _e.g._ an
+//! interrupt handler that pushes received bytes and a main loop that pops them,
+//! as each index is written by one side only. Index accesses are volatile so that
+//! the compiler does not cache them across interrupts.
+
+use core::ptr::{addr_of, addr_of_mut};
+
+/// Queue of up to `N - 1` bytes, with `N` at most 256
+pub struct RingBuffer<const N: usize> {
+    data: [u8; N],
+    /// Next position to read; written by the consumer only
+    head: u8,
+    /// Next position to write; written by the producer only
+    tail: u8,
+}
+
+impl<const N: usize> RingBuffer<N> {
+    /// New, empty buffer
+    pub const fn new() -> Self {
+        assert!(N >= 2 && N <= 256);
+        Self {
+            data: [0; N],
+            head: 0,
+            tail: 0,
+        }
+    }
+
+    fn head(&self) -> usize {
+        unsafe { addr_of!(self.head).read_volatile() as usize }
+    }
+
+    fn tail(&self) -> usize {
+        unsafe { addr_of!(self.tail).read_volatile() as usize }
+    }
+
+    const fn next(index: usize) -> usize {
+        (index + 1) % N
+    }
+
+    /// Append `byte`; returns `false` if the buffer is full
+    pub fn push(&mut self, byte: u8) -> bool {
+        let tail = self.tail();
+        let next = Self::next(tail);
+        if next == self.head() {
+            return false;
+        }
+        self.data[tail] = byte;
+        unsafe { addr_of_mut!(self.tail).write_volatile(next as u8) };
+        true
+    }
+
+    /// Remove and return the oldest byte
+    pub fn pop(&mut self) -> Option<u8> {
+        let head = self.head();
+        if head == self.tail() {
+            return None;
+        }
+        let byte = self.data[head];
+        unsafe { addr_of_mut!(self.head).write_volatile(Self::next(head) as u8) };
+        Some(byte)
+    }
+
+    /// Oldest byte without removing it
+    pub fn peek(&self) -> Option<u8> {
+        let head = self.head();
+        match head == self.tail() {
+            true => None,
+            false => Some(self.data[head]),
+        }
+    }
+
+    /// Number of queued bytes
+    pub fn len(&self) -> usize {
+        (self.tail() + N - self.head()) % N
+    }
+
+    /// True if no bytes are queued
+    pub fn is_empty(&self) -> bool {
+        self.head() == self.tail()
+    }
+
+    /// True if no more bytes can be pushed
+    pub fn is_full(&self) -> bool {
+        Self::next(self.tail()) == self.head()
+    }
+
+    /// Number of bytes that can be pushed
+    pub fn free(&self) -> usize {
+        N - 1 - self.len()
+    }
+
+    /// Discard all queued bytes; called by the consumer
+    pub fn clear(&mut self) {
+        unsafe { addr_of_mut!(self.head).write_volatile(self.tail() as u8) };
+    }
+}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,213 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Interrupt-driven driver for the CIA serial shift register (SDR)
+//!
+//! In output mode, timer A runs continuously and each byte written to the shift
+//! register is clocked out MSB first on the SP pin, one bit per two timer A
+//! underflows, with the clock on the CNT pin. In input mode, a bit is shifted in
+//! from SP on every rising edge of CNT, clocked by the other end. After eight
+//! bits, the CIA raises the `SERIAL` interrupt, from which `on_interrupt()` must
+//! be called. On the C64, SP and CNT of both CIAs are found on the user port,
+//! which can link two machines.
+//!
+//! The driver is shared with the interrupt handler, which may be an NMI that
+//! `interrupt::free()` does not hold off. `write()` therefore masks the `SERIAL`
+//! source in the CIA while it hands a byte over, and received bytes are passed
+//! through a single producer, single consumer `RingBuffer`. Other methods must
+//! not be called while the `SERIAL` interrupt is enabled, except `read()`,
+//! `available()`, `is_flushed()` and `overruns()`.
+//!
+//! # Examples
+//!
+//! CIA2 interrupts are delivered through NMI, so the shift register can be driven
+//! without disturbing the KERNAL IRQ that uses timer A of CIA1:
+//! ~~~
+//! use mos_hardware::serial_shift::{self, SerialShift};
+//!
+//! static mut LINK: SerialShift<32> = SerialShift::new(c64::cia2_control());
+//!
+//! irq::install(irq::Vectors::Kernal);
+//! irq::set_handler(irq::Interrupt::Cia2Serial, || unsafe { LINK.on_interrupt() });
+//! let latch = serial_shift::timer_latch(9600, cia::PAL_CLOCK_HZ);
+//! unsafe {
+//!     LINK.start_output(latch);
+//!     LINK.write(0x42);
+//! }
+//! ~~~
+
+use crate::cia::{InterruptControl, Mos6526ControlBlock, Timer, TimerControl, TimerMode};
+use crate::ring_buffer::RingBuffer;
+use core::ptr::{addr_of, addr_of_mut};
+
+/// Timer A latch for an output bit rate; the CIA needs at least 1
+///
+/// The system clock `clock_hz` is _e.g._ `cia::PAL_CLOCK_HZ` or `detect::c64().clock_hz`.
+pub const fn timer_latch(bits_per_second: u32, clock_hz: u32) -> u16 {
+    let latch = clock_hz / (2 * bits_per_second);
+    match latch {
+        0..=1 => 1,
+        _ => (latch - 1) as u16,
+    }
+}
+
+/// Direction of the shift register
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    /// Not started or stopped
+    Idle,
+    /// Shift out, clocked by timer A
+    Output,
+    /// Shift in, clocked by the CNT pin
+    Input,
+}
+
+/// Shift register driver with `N`-byte transmit and receive buffers
+pub struct SerialShift<const N: usize> {
+    control: &'static Mos6526ControlBlock,
+    direction: Direction,
+    /// True while a byte is being shifted out; cleared by the interrupt handler
+    sending: bool,
+    transmit: RingBuffer<N>,
+    receive: RingBuffer<N>,
+    /// Received bytes dropped because the receive buffer was full
+    overruns: u16,
+}
+
+impl<const N: usize> SerialShift<N> {
+    /// New driver for the shift register of a CIA, _e.g._ `c64::cia1_control()`
+    pub const fn new(control: &'static Mos6526ControlBlock) -> Self {
+        Self {
+            control,
+            direction: Direction::Idle,
+            sending: false,
+            transmit: RingBuffer::new(),
+            receive: RingBuffer::new(),
+            overruns: 0,
+        }
+    }
+
+    /// Current direction
+    pub const fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Shift out bytes with timer A running continuously from `latch`, see `timer_latch()`
+    ///
+    /// Timer A is used exclusively for the bit rate, and the `SERIAL` interrupt is enabled.
+    pub fn start_output(&mut self, latch: u16) {
+        self.direction = Direction::Output;
+        self.sending = false;
+        unsafe { self.control.control_a.modify(|v| v | TimerControl::SPMODE) };
+        self.control
+            .start_timer(Timer::A, latch, TimerMode::Continuous);
+        self.enable_interrupt(true);
+    }
+
+    /// Shift in bytes clocked by the CNT pin and enable the `SERIAL` interrupt
+    pub fn start_input(&mut self) {
+        self.direction = Direction::Input;
+        self.sending = false;
+        unsafe { self.control.control_a.modify(|v| v & !TimerControl::SPMODE) };
+        self.enable_interrupt(true);
+    }
+
+    /// Disable the `SERIAL` interrupt; bytes being shifted are lost
+    pub fn stop(&mut self) {
+        self.enable_interrupt(false);
+        if self.direction == Direction::Output {
+            self.control.stop_timer(Timer::A);
+        }
+        self.direction = Direction::Idle;
+        self.sending = false;
+    }
+
+    fn enable_interrupt(&self, enabled: bool) {
+        let flags = match enabled {
+            true => InterruptControl::SET_CLEAR | InterruptControl::SERIAL,
+            false => InterruptControl::SERIAL,
+        };
+        unsafe { self.control.interrupt.write(flags) };
+    }
+
+    fn is_sending(&self) -> bool {
+        unsafe { addr_of!(self.sending).read_volatile() }
+    }
+
+    fn set_sending(&mut self, sending: bool) {
+        unsafe { addr_of_mut!(self.sending).write_volatile(sending) }
+    }
+
+    /// Queue `byte` for output; returns `false` if the transmit buffer is full
+    ///
+    /// The `SERIAL` source is masked in the CIA meanwhile, as SEI does not hold
+    /// off the NMI of CIA2. A byte completed in that time raises the interrupt
+    /// once the source is unmasked.
+    pub fn write(&mut self, byte: u8) -> bool {
+        self.enable_interrupt(false);
+        let queued = match self.direction == Direction::Output && !self.is_sending() {
+            true => {
+                self.set_sending(true);
+                unsafe { self.control.serial_shift.write(byte) };
+                true
+            }
+            false => self.transmit.push(byte),
+        };
+        if self.direction != Direction::Idle {
+            self.enable_interrupt(true);
+        }
+        queued
+    }
+
+    /// Queue as many bytes as fit; returns the number of queued bytes
+    pub fn write_all(&mut self, bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|byte| self.write(**byte)).count()
+    }
+
+    /// Take the oldest received byte
+    pub fn read(&mut self) -> Option<u8> {
+        self.receive.pop()
+    }
+
+    /// Number of received bytes waiting to be read
+    pub fn available(&self) -> usize {
+        self.receive.len()
+    }
+
+    /// True if all bytes have been shifted out
+    pub fn is_flushed(&self) -> bool {
+        !self.is_sending() && self.transmit.is_empty()
+    }
+
+    /// Number of received bytes dropped because the receive buffer was full
+    pub const fn overruns(&self) -> u16 {
+        self.overruns
+    }
+
+    /// Handle the `SERIAL` interrupt: send the next queued byte or store the received one
+    pub fn on_interrupt(&mut self) {
+        match self.direction {
+            Direction::Output => match self.transmit.pop() {
+                Some(byte) => unsafe { self.control.serial_shift.write(byte) },
+                None => self.set_sending(false),
+            },
+            Direction::Input => {
+                if !self.receive.push(self.control.serial_shift.read()) {
+                    self.overruns = self.overruns.saturating_add(1);
+                }
+            }
+            Direction::Idle => {}
+        }
+    }
+}
@@ -18,11 +18,11 @@
 //! Commodore 64, Commodore 128 and Commodore MAX Machine home computers.
 //! It was one of the first sound chips of its kind to be included in a home computer.
 
+use crate::bus::{Bus, Hardware, RO, WO};
 use bitflags::bitflags;
 use core::mem::size_of;
 use rand_core::{Error, RngCore};
 use static_assertions::const_assert;
-use volatile_register::{RO, WO};
 
 bitflags! {
     /// Control flags for the `Voice::control` register
@@ -106,6 +106,57 @@ pub const fn combine_attack_decay(attack_time: AttackTime, decay_time: DecayTime
     (attack_time as u8 * 16) + (decay_time as u8)
 }
 
+/// Number of notes in `note_table()`, C-0 to B-7
+pub const NOTES: usize = 96;
+
+/// Frequencies of C-7 to B-7 in centihertz, tuned to A-4 = 440 Hz
+const TOP_OCTAVE: [u32; 12] = [
+    209300, 221746, 234932, 248902, 263702, 279383, 295996, 313596, 332244, 352000, 372931, 395107,
+];
+
+/// Frequency register value for a tone in centihertz and a system clock in Hz
+///
+/// Saturates above the highest frequency of the SID (about 3.9 kHz).
+///
+/// ## Example:
+/// ~~~
+/// const A4: u16 = sid::frequency_register(44000, cia::PAL_CLOCK_HZ); // 7493
+/// ~~~
+pub const fn frequency_register(centihertz: u32, clock_hz: u32) -> u16 {
+    rounded_register(centihertz, clock_hz as u64 * 100)
+}
+
+/// `centihertz` × 2^24 / `divisor`, rounded and saturated
+const fn rounded_register(centihertz: u32, divisor: u64) -> u16 {
+    let value = (((centihertz as u64) << 24) + divisor / 2) / divisor;
+    match value > u16::MAX as u64 {
+        true => u16::MAX,
+        false => value as u16,
+    }
+}
+
+/// Frequency register values of equal tempered notes from C-0 to B-7
+///
+/// The clock depends on the video standard, see `detect::VideoStandard::clock_hz()`.
+/// B-7 is above the range of the SID and saturates.
+///
+/// ## Example:
+/// ~~~
+/// const PAL_NOTES: [u16; sid::NOTES] = sid::note_table(cia::PAL_CLOCK_HZ);
+/// let notes = sid::note_table(detect::c64().clock_hz);
+/// ~~~
+pub const fn note_table(clock_hz: u32) -> [u16; NOTES] {
+    let mut table = [0; NOTES];
+    let mut note = 0;
+    while note < NOTES {
+        // lower octaves halve the frequency of the top octave
+        let divisor = (clock_hz as u64 * 100) << (7 - note / 12);
+        table[note] = rounded_register(TOP_OCTAVE[note % 12], divisor);
+        note += 1;
+    }
+    table
+}
+
 impl Voice {
     /// Sets the attack/decay cycle duration (`ATDCY`)
     ///
@@ -125,6 +176,134 @@ impl Voice {
 
 const_assert!(size_of::<Voice>() == 7);
 
+bitflags! {
+    /// Voices routed through the filter, bits 0-3 of `RESON` (0x17)
+    pub struct FilterVoices: u8 {
+        const VOICE1 = 0b0000_0001;
+        const VOICE2 = 0b0000_0010;
+        const VOICE3 = 0b0000_0100;
+        /// External audio input
+        const EXTERNAL = 0b0000_1000;
+    }
+}
+
+bitflags! {
+    /// Filter modes, bits 4-6 of `SIGVOL` (0x18)
+    ///
+    /// Modes can be combined, _e.g._ low pass and high pass gives a notch filter.
+    pub struct FilterMode: u8 {
+        const LOW_PASS = 0b0001_0000;
+        const BAND_PASS = 0b0010_0000;
+        const HIGH_PASS = 0b0100_0000;
+    }
+}
+
+/// Filter resonance and voice routing (`RESON`, 0x17)
+///
+/// The SID registers are write-only so the full value must be composed before
+/// writing it.
+///
+/// # Examples
+/// ~~~
+/// let setup = FilterSetup::new().resonance(12).voices(FilterVoices::VOICE1);
+/// unsafe { c64::sid().resonance_and_filter_setup.write(setup) };
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct FilterSetup(u8);
+
+impl FilterSetup {
+    /// No voices filtered and zero resonance
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set voices to route through the filter
+    pub const fn voices(self, voices: FilterVoices) -> Self {
+        Self((self.0 & 0xf0) | voices.bits())
+    }
+
+    /// Set filter resonance (0-15)
+    pub const fn resonance(self, resonance: u8) -> Self {
+        assert!(resonance < 16);
+        Self((self.0 & 0x0f) | (resonance << 4))
+    }
+
+    /// Voices routed through the filter
+    pub const fn get_voices(&self) -> FilterVoices {
+        FilterVoices::from_bits_truncate(self.0)
+    }
+
+    /// Filter resonance (0-15)
+    pub const fn get_resonance(&self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+/// Volume and filter mode (`SIGVOL`, 0x18)
+///
+/// # Examples
+/// ~~~
+/// let mode = VolumeFilterMode::new().volume(15).mode(FilterMode::LOW_PASS);
+/// unsafe { c64::sid().volume_filter_mode.write(mode) };
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct VolumeFilterMode(u8);
+
+impl VolumeFilterMode {
+    const VOICE3_OFF: u8 = 0b1000_0000;
+
+    /// Zero volume, no filter, and voice 3 on
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set master volume (0-15)
+    pub const fn volume(self, volume: u8) -> Self {
+        assert!(volume < 16);
+        Self((self.0 & 0xf0) | volume)
+    }
+
+    /// Set filter mode
+    pub const fn mode(self, mode: FilterMode) -> Self {
+        Self((self.0 & !FilterMode::all().bits()) | mode.bits())
+    }
+
+    /// Disconnect voice 3 from the audio output, _e.g._ when used as a modulator or random generator
+    pub const fn voice3_off(self, off: bool) -> Self {
+        match off {
+            true => Self(self.0 | Self::VOICE3_OFF),
+            false => Self(self.0 & !Self::VOICE3_OFF),
+        }
+    }
+
+    /// Master volume (0-15)
+    pub const fn get_volume(&self) -> u8 {
+        self.0 & 0x0f
+    }
+
+    /// Filter mode
+    pub const fn get_mode(&self) -> FilterMode {
+        FilterMode::from_bits_truncate(self.0)
+    }
+
+    /// True if voice 3 is disconnected from the audio output
+    pub const fn get_voice3_off(&self) -> bool {
+        self.0 & Self::VOICE3_OFF != 0
+    }
+}
+
 #[repr(C, packed)]
 /// MOS Technology Sound Interface Device (SID)
 pub struct MOSSoundInterfaceDevice {
@@ -133,9 +312,9 @@ pub struct MOSSoundInterfaceDevice {
     pub channel3: Voice,
     pub filter_cutoff: WO<u16>, // 0x15
     /// `RESON` Filter resonance control (0x17)
-    pub resonance_and_filter_setup: WO<u8>,
+    pub resonance_and_filter_setup: WO<FilterSetup>,
     /// `SIGVOL` Volume and filter select (0x18)
-    pub volume_filter_mode: WO<u8>,
+    pub volume_filter_mode: WO<VolumeFilterMode>,
     pub potentiometer_x: RO<u8>,     // 0x19
     pub potentiometer_y: RO<u8>,     // 0x1a
     pub channel3_oscillator: RO<u8>, // 0x1b
@@ -148,7 +327,7 @@ impl MOSSoundInterfaceDevice {
     /// Reset like Kernal: turn off SID
     pub fn reset(&self) {
         unsafe {
-            self.volume_filter_mode.write(0);
+            self.volume_filter_mode.write(VolumeFilterMode::new());
         }
     }
 
@@ -348,16 +527,39 @@ pub trait SidTune {
     /// It would be nice to let the compiler decide where to place the
     /// wrapper code (`address`), but so far no luck.
     fn init(&self, song: u8) {
-        let [high, low] = Self::INIT_ADDRESS.to_be_bytes();
-        let address = Self::LOAD_ADDRESS as usize + Self::DATA_LEN;
+        let address = unsafe { self.write_init_wrapper(&Hardware, song) } as usize;
         let init_fn = &address as *const usize as *const unsafe extern "C" fn() -> ();
         unsafe {
-            // 0xa9 = lda; 0x4c = jmp
-            *(address as *mut [u8; 5]) = [0xa9, song, 0x4c, low, high];
             (*init_fn)();
         }
     }
 
+    /// Write the 6502 wrapper code used by `init()` and return its address
+    ///
+    /// The wrapper loads the `song` number into the accumulator and jumps to the init routine.
+    /// It is placed right after the song data.
+    ///
+    /// # Examples
+    /// ~~~
+    /// use mos_hardware::bus::HostBus;
+    /// let bus = HostBus::new();
+    /// let address = unsafe { Music.write_init_wrapper(&bus, 3) };
+    /// assert_eq!(bus.peek(address + 1), 3);
+    /// ~~~
+    ///
+    /// # Safety
+    /// Unsafe, as this writes five bytes after the song data
+    unsafe fn write_init_wrapper<B: Bus>(&self, bus: &B, song: u8) -> u16 {
+        let [high, low] = Self::INIT_ADDRESS.to_be_bytes();
+        let address = Self::LOAD_ADDRESS.wrapping_add(Self::DATA_LEN as u16);
+        // 0xa9 = lda; 0x4c = jmp
+        [0xa9, song, 0x4c, low, high]
+            .iter()
+            .enumerate()
+            .for_each(|(i, byte)| bus.write(address + i as u16, *byte));
+        address
+    }
+
     /// Call song play routine
     fn play(&self) {
         unsafe { (*(Self::PLAY_PTR))() }
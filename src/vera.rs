@@ -51,9 +51,9 @@
 //! - SPI controller for SecureDigital storage.
 //! - [VERA Reference Guide](https://github.com/commanderx16/x16-docs/blob/master/VERA%20Programmer's%20Reference.md)
 
+use crate::bus::{RW, WO};
 use bitflags::bitflags;
 use core::mem::ManuallyDrop;
-use volatile_register::{RW, WO};
 
 pub const VIDEOMODE_80X60: u8 = 0;
 pub const VIDEOMODE_80X30: u8 = 1;
@@ -277,17 +277,212 @@ pub struct Display1 {
 #[repr(C)]
 pub struct Layer {
     /// `Lx_CONFIG`
-    pub config: RW<u8>,
+    pub config: RW<LayerConfig>,
     /// `Lx_MAPBASE` - Map Base Address (16:9)
     pub mapbase: RW<u8>,
-    /// `Lx_TILEBASE`
-    pub tilebase: RW<u8>,
+    /// `Lx_TILEBASE` - Tile Base Address (16:11) and tile size
+    pub tilebase: RW<TileBase>,
     /// `H-SCROLL` - Horizontal scroll
     pub hscroll: RW<u16>,
     /// `V-SCROLL` - Vertical scroll
     pub vscroll: RW<u16>,
 }
 
+/// Color depth of a layer, bits 0-1 of `Lx_CONFIG`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum ColorDepth {
+    /// 1 bit per pixel
+    Bpp1 = 0,
+    /// 2 bits per pixel
+    Bpp2 = 1,
+    /// 4 bits per pixel
+    Bpp4 = 2,
+    /// 8 bits per pixel
+    Bpp8 = 3,
+}
+
+/// Tile map width or height, bits 4-5 and 6-7 of `Lx_CONFIG`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum MapSize {
+    Tiles32 = 0,
+    Tiles64 = 1,
+    Tiles128 = 2,
+    Tiles256 = 3,
+}
+
+impl MapSize {
+    const fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Self::Tiles32,
+            1 => Self::Tiles64,
+            2 => Self::Tiles128,
+            _ => Self::Tiles256,
+        }
+    }
+}
+
+/// Layer configuration (`Lx_CONFIG`)
+///
+/// # Examples
+/// ~~~
+/// (*cx16::VERA).layer1.config.modify(|r| {
+///     r.color_depth(ColorDepth::Bpp4).map_width(MapSize::Tiles128)
+/// });
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct LayerConfig(u8);
+
+impl LayerConfig {
+    const COLOR_DEPTH_MASK: u8 = 0b0000_0011;
+    const BITMAP_MODE: u8 = 0b0000_0100;
+    const T256C: u8 = 0b0000_1000;
+    const MAP_WIDTH_SHIFT: u8 = 4;
+    const MAP_HEIGHT_SHIFT: u8 = 6;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set color depth
+    pub const fn color_depth(self, depth: ColorDepth) -> Self {
+        Self((self.0 & !Self::COLOR_DEPTH_MASK) | depth as u8)
+    }
+
+    /// Select bitmap mode (`true`) or tile mode (`false`)
+    pub const fn bitmap_mode(self, enable: bool) -> Self {
+        Self((self.0 & !Self::BITMAP_MODE) | if enable { Self::BITMAP_MODE } else { 0 })
+    }
+
+    /// Use 256-color foreground in 1 bpp tile mode
+    pub const fn t256c(self, enable: bool) -> Self {
+        Self((self.0 & !Self::T256C) | if enable { Self::T256C } else { 0 })
+    }
+
+    /// Set tile map width
+    pub const fn map_width(self, size: MapSize) -> Self {
+        let mask = 0b11 << Self::MAP_WIDTH_SHIFT;
+        Self((self.0 & !mask) | ((size as u8) << Self::MAP_WIDTH_SHIFT))
+    }
+
+    /// Set tile map height
+    pub const fn map_height(self, size: MapSize) -> Self {
+        let mask = 0b11 << Self::MAP_HEIGHT_SHIFT;
+        Self((self.0 & !mask) | ((size as u8) << Self::MAP_HEIGHT_SHIFT))
+    }
+
+    /// Color depth
+    pub const fn get_color_depth(&self) -> ColorDepth {
+        match self.0 & Self::COLOR_DEPTH_MASK {
+            0 => ColorDepth::Bpp1,
+            1 => ColorDepth::Bpp2,
+            2 => ColorDepth::Bpp4,
+            _ => ColorDepth::Bpp8,
+        }
+    }
+
+    /// True if in bitmap mode
+    pub const fn get_bitmap_mode(&self) -> bool {
+        self.0 & Self::BITMAP_MODE != 0
+    }
+
+    /// True if 256-color foreground is used in 1 bpp tile mode
+    pub const fn get_t256c(&self) -> bool {
+        self.0 & Self::T256C != 0
+    }
+
+    /// Tile map width
+    pub const fn get_map_width(&self) -> MapSize {
+        MapSize::from_bits(self.0 >> Self::MAP_WIDTH_SHIFT)
+    }
+
+    /// Tile map height
+    pub const fn get_map_height(&self) -> MapSize {
+        MapSize::from_bits(self.0 >> Self::MAP_HEIGHT_SHIFT)
+    }
+}
+
+/// Tile width or height, bits 0 and 1 of `Lx_TILEBASE`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum TileSize {
+    Pixels8 = 0,
+    Pixels16 = 1,
+}
+
+/// Tile base address and tile size (`Lx_TILEBASE`)
+///
+/// # Examples
+/// ~~~
+/// (*cx16::VERA).layer0.tilebase.modify(|r| {
+///     r.address(0x1f000).tile_width(TileSize::Pixels16)
+/// });
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(transparent)]
+pub struct TileBase(u8);
+
+impl TileBase {
+    const ADDRESS_MASK: u8 = 0b1111_1100;
+    const TILE_HEIGHT: u8 = 0b0000_0010;
+    const TILE_WIDTH: u8 = 0b0000_0001;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set tile base address in VRAM; must be a multiple of 2048 bytes
+    pub const fn address(self, address: u32) -> Self {
+        assert!(address.is_multiple_of(2048) && address < 0x20000);
+        Self((self.0 & !Self::ADDRESS_MASK) | (address >> 9) as u8)
+    }
+
+    /// Set tile height
+    pub const fn tile_height(self, size: TileSize) -> Self {
+        Self((self.0 & !Self::TILE_HEIGHT) | ((size as u8) << 1))
+    }
+
+    /// Set tile width
+    pub const fn tile_width(self, size: TileSize) -> Self {
+        Self((self.0 & !Self::TILE_WIDTH) | size as u8)
+    }
+
+    /// Tile base address in VRAM
+    pub const fn get_address(&self) -> u32 {
+        ((self.0 & Self::ADDRESS_MASK) as u32) << 9
+    }
+
+    /// Tile height
+    pub const fn get_tile_height(&self) -> TileSize {
+        match self.0 & Self::TILE_HEIGHT {
+            0 => TileSize::Pixels8,
+            _ => TileSize::Pixels16,
+        }
+    }
+
+    /// Tile width
+    pub const fn get_tile_width(&self) -> TileSize {
+        match self.0 & Self::TILE_WIDTH {
+            0 => TileSize::Pixels8,
+            _ => TileSize::Pixels16,
+        }
+    }
+}
+
 /// VERA audio
 ///
 /// The audio functionality consists of two independent systems:
@@ -321,7 +516,7 @@ pub struct SPIController {
 }
 
 /// VRAM, 0x00000 - 0x1F9BF
-pub const VIDEO_RAM: *mut u8 = (0x00000) as *mut u8;
+pub const VIDEO_RAM: *mut u8 = core::ptr::null_mut();
 
 /// PSG registers, 0x1F9C0 - 0x1F9FF
 pub const PSG_REGISTERS: *mut u8 = (0x1f9c0u32) as *mut u8;
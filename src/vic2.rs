@@ -19,10 +19,10 @@
 //! with generating Y/C video signals (combined to composite video in the RF modulator)
 //! and DRAM refresh signals in the Commodore 64 and C128 home computers.
 
+use crate::bus::{RO, RW};
 use bitflags::bitflags;
 use core::mem::size_of;
 use static_assertions::const_assert;
-use volatile_register::{RO, RW};
 
 pub const BLACK: u8 = 0;
 pub const WHITE: u8 = 1;
@@ -142,8 +142,8 @@ bitflags! {
     ///
     /// # Examples
     /// ~~~
-    /// let bank = vic2::ScreenBank::AT_2C00.bits() | vic2::CharsetBank::AT_2000.bits();
-    /// (*c64::VIC).screen_and_charset_bank.write(bank);
+    /// let setup = MemorySetup::from_bits(0).screen(ScreenBank::AT_2C00).charset(CharsetBank::AT_2000);
+    /// c64::vic2().screen_and_charset_bank.write(setup);
     /// ~~~
     pub struct CharsetBank: u8 {
         const AT_0000 = 0b0000_0000;
@@ -228,6 +228,87 @@ impl ScreenBank {
     }
 }
 
+/// Memory setup register fields (`VMCSB`, 0xd018)
+///
+/// Selects where, relative to the VIC bank, the VIC-II reads screen memory
+/// (bits 4-7) and the character set (bits 1-3). In bitmap mode, bit 3 selects the
+/// bitmap at 0x0000 or 0x2000.
+///
+/// # Examples
+/// ~~~
+/// c64::vic2().screen_and_charset_bank.modify(|r| {
+///     r.screen(ScreenBank::AT_0400).charset(CharsetBank::AT_1800)
+/// });
+/// let charset = c64::vic2().screen_and_charset_bank.read().get_charset();
+/// ~~~
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct MemorySetup(u8);
+
+impl MemorySetup {
+    const SCREEN_MASK: u8 = 0b1111_0000;
+    const CHARSET_MASK: u8 = 0b0000_1110;
+
+    /// Construct from raw register value
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Raw register value
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Set screen memory location, leaving other bits unchanged
+    pub const fn screen(self, bank: ScreenBank) -> Self {
+        Self((self.0 & !Self::SCREEN_MASK) | bank.bits())
+    }
+
+    /// Set character memory location, leaving other bits unchanged
+    pub const fn charset(self, bank: CharsetBank) -> Self {
+        Self((self.0 & !Self::CHARSET_MASK) | bank.bits())
+    }
+
+    /// Screen memory location
+    pub const fn get_screen(&self) -> ScreenBank {
+        ScreenBank::from_bits_truncate(self.0)
+    }
+
+    /// Character memory location
+    pub const fn get_charset(&self) -> CharsetBank {
+        CharsetBank::from_bits_truncate(self.0)
+    }
+}
+
+impl ControlYFlags {
+    /// Set smooth y scrolling (0-7), leaving other bits unchanged
+    pub const fn yscroll(self, pixels: u8) -> Self {
+        Self::from_bits_truncate((self.bits & !Self::YSCROLL.bits) | (pixels & Self::YSCROLL.bits))
+    }
+
+    /// Smooth y scrolling (0-7)
+    pub const fn get_yscroll(&self) -> u8 {
+        self.bits & Self::YSCROLL.bits
+    }
+}
+
+impl ControlXFlags {
+    /// Set smooth x scrolling (0-7), leaving other bits unchanged
+    ///
+    /// # Examples
+    /// ~~~
+    /// c64::vic2().control_x.modify(|r| r.xscroll(3));
+    /// ~~~
+    pub const fn xscroll(self, pixels: u8) -> Self {
+        Self::from_bits_truncate((self.bits & !Self::XSCROLL.bits) | (pixels & Self::XSCROLL.bits))
+    }
+
+    /// Smooth x scrolling (0-7)
+    pub const fn get_xscroll(&self) -> u8 {
+        self.bits & Self::XSCROLL.bits
+    }
+}
+
 /// X and Y positions for e.g. sprites
 pub struct XYcoordinate {
     /// X position
@@ -267,7 +348,7 @@ pub struct MOSVideoInterfaceControllerII {
     /// `YXPAND` (0x17)
     pub sprite_expand_y: RW<Sprites>,
     /// `VMCSB` Memory Control Register (0x18)
-    pub screen_and_charset_bank: RW<u8>,
+    pub screen_and_charset_bank: RW<MemorySetup>,
     /// `VICIRQ` Interrupt flag (0x19)
     ///
     /// The VIC-II chip is capable of generating a maskable request (IRQ)
@@ -314,12 +395,193 @@ impl MOSVideoInterfaceControllerII {
         }
     }
 
+    /// Sets the 9-bit X (0-511) and Y position of a sprite, including its bit in `MSIGX`
+    pub fn set_sprite_position(&self, index: u8, x: u16, y: u8) {
+        let sprite = Sprites::new(index);
+        unsafe {
+            self.sprite_positions_most_significant_bit_of_x
+                .modify(|mut msb| {
+                    msb.set(sprite, x > 0xff);
+                    msb
+                });
+        }
+        self.set_sprite_pos(index, x as u8, y);
+    }
+
+    /// 9-bit X position of a sprite
+    pub fn get_sprite_x(&self, index: u8) -> u16 {
+        let msb = self
+            .sprite_positions_most_significant_bit_of_x
+            .read()
+            .contains(Sprites::new(index));
+        ((msb as u16) << 8) | self.sprite_positions[index as usize].x.read() as u16
+    }
+
+    /// Set the 9-bit raster line (0-311 on PAL) that triggers the raster compare interrupt
+    ///
+    /// Bit 8 of the line is stored in the `RASTER_COMPARE` bit of `control_y`.
+    pub fn set_raster_irq_line(&self, line: u16) {
+        unsafe {
+            self.control_y.modify(|mut control| {
+                control.set(ControlYFlags::RASTER_COMPARE, line > 0xff);
+                control
+            });
+            self.raster_counter.write(line as u8);
+        }
+    }
+
+    /// Current 9-bit raster line
+    ///
+    /// The high bit is read before and after the low byte to get a consistent
+    /// value when the beam passes from line 255 to 256.
+    pub fn get_raster_line(&self) -> u16 {
+        loop {
+            let high = self.control_y.read() & ControlYFlags::RASTER_COMPARE;
+            let low = self.raster_counter.read();
+            if high == self.control_y.read() & ControlYFlags::RASTER_COMPARE {
+                return ((!high.is_empty() as u16) << 8) | low as u16;
+            }
+        }
+    }
+
     /// Sets color of sprite identified by it's index
     pub fn set_sprite_color(&self, index: u8, color: u8) {
         unsafe {
             self.sprite_colors[index as usize].write(color);
         }
     }
+
+    /// Switch graphics mode, leaving scrolling and other bits unchanged
+    ///
+    /// # Examples
+    /// ~~~
+    /// let vic = c64::vic2();
+    /// vic.set_graphics_mode(GraphicsMode::ExtendedColorText);
+    /// vic.set_background_color(1, vic2::RED);
+    /// ~~~
+    pub fn set_graphics_mode(&self, mode: GraphicsMode) {
+        let (extended_color, bitmap, multicolor) = mode.flags();
+        unsafe {
+            self.control_y.modify(|mut v| {
+                v.set(ControlYFlags::EXTENDED_COLOR_MODE, extended_color);
+                v.set(ControlYFlags::BITMAP_MODE, bitmap);
+                v
+            });
+            self.control_x.modify(|mut v| {
+                v.set(ControlXFlags::MULTICOLOR, multicolor);
+                v
+            });
+        }
+    }
+
+    /// Current graphics mode; `None` for the invalid combinations that show a black screen
+    pub fn get_graphics_mode(&self) -> Option<GraphicsMode> {
+        GraphicsMode::from_flags(
+            self.control_y
+                .read()
+                .contains(ControlYFlags::EXTENDED_COLOR_MODE),
+            self.control_y.read().contains(ControlYFlags::BITMAP_MODE),
+            self.control_x.read().contains(ControlXFlags::MULTICOLOR),
+        )
+    }
+
+    /// Set background color `index` (0-3), used by extended color and multicolor modes
+    pub fn set_background_color(&self, index: u8, color: u8) {
+        let register = match index {
+            0 => &self.background_color0,
+            1 => &self.background_color1,
+            2 => &self.background_color2,
+            3 => &self.background_color3,
+            _ => panic!("background color index must be 0-3"),
+        };
+        unsafe { register.write(color) };
+    }
+}
+
+/// Graphics modes selected by the `ECM`, `BMM` and `MCM` bits
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GraphicsMode {
+    /// 40×25 characters with one color per cell on a common background
+    StandardText,
+    /// Characters with cells either in standard or in multicolor, see `multicolor_text_color()`
+    MulticolorText,
+    /// 64 characters with one of four backgrounds per cell, see `extended_color_cell()`
+    ExtendedColorText,
+    /// 320×200 pixels with two colors per cell
+    HiresBitmap,
+    /// 160×200 pixels with four colors per cell
+    MulticolorBitmap,
+}
+
+impl GraphicsMode {
+    /// Mode from the extended color, bitmap and multicolor bits
+    ///
+    /// Returns `None` for the invalid modes combining extended color with
+    /// bitmap or multicolor mode, which show a black screen.
+    pub const fn from_flags(extended_color: bool, bitmap: bool, multicolor: bool) -> Option<Self> {
+        match (extended_color, bitmap, multicolor) {
+            (false, false, false) => Some(Self::StandardText),
+            (false, false, true) => Some(Self::MulticolorText),
+            (true, false, false) => Some(Self::ExtendedColorText),
+            (false, true, false) => Some(Self::HiresBitmap),
+            (false, true, true) => Some(Self::MulticolorBitmap),
+            (true, _, _) => None,
+        }
+    }
+
+    /// Extended color, bitmap and multicolor bits of the mode
+    pub const fn flags(&self) -> (bool, bool, bool) {
+        match self {
+            Self::StandardText => (false, false, false),
+            Self::MulticolorText => (false, false, true),
+            Self::ExtendedColorText => (true, false, false),
+            Self::HiresBitmap => (false, true, false),
+            Self::MulticolorBitmap => (false, true, true),
+        }
+    }
+
+    /// True for the text modes
+    pub const fn is_text(&self) -> bool {
+        !self.flags().1
+    }
+}
+
+/// Screen code for a cell in extended color mode
+///
+/// Only the first 64 characters (`screen_code` 0-63) are available, and
+/// `background` (0-3) selects one of the four background colors.
+///
+/// # Examples
+/// ~~~
+/// const CELL: u8 = vic2::extended_color_cell(1, 2); // 'A' on background color 2
+/// ~~~
+pub const fn extended_color_cell(screen_code: u8, background: u8) -> u8 {
+    assert!(screen_code < 64, "extended color mode has 64 characters");
+    assert!(background < 4, "background index must be 0-3");
+    (background << 6) | screen_code
+}
+
+/// Screen code (0-63) and background index (0-3) of a cell in extended color mode
+pub const fn get_extended_color_cell(cell: u8) -> (u8, u8) {
+    (cell & 0x3f, cell >> 6)
+}
+
+/// Color RAM value for a cell in multicolor text mode
+///
+/// Multicolor cells use bit 3 of color RAM, leaving colors 0-7 for the
+/// foreground (pixel pair `11`); pairs `01` and `10` use background colors 1 and 2.
+/// Standard cells can use colors 0-7 only.
+///
+/// # Examples
+/// ~~~
+/// const MULTICOLOR_GREEN: u8 = vic2::multicolor_text_color(vic2::GREEN, true);
+/// ~~~
+pub const fn multicolor_text_color(color: u8, multicolor: bool) -> u8 {
+    assert!(color < 8, "multicolor text mode has foreground colors 0-7");
+    match multicolor {
+        true => color | 0x08,
+        false => color,
+    }
 }
 
 /// Calculate sprite pointer from pattern address
@@ -361,7 +623,129 @@ impl MOSVideoInterfaceControllerII {
 ///     print(int(''.join('01'[i] for i in bits_in_byte), 2), end=',')
 /// ~~~
 pub const fn to_sprite_pointer(address: u16) -> u8 {
-    assert!(address % 64 == 0);
+    assert!(address.is_multiple_of(64));
     assert!(address / 64 < 256);
     (address / 64) as u8
 }
+
+/// Bits of an ASCII art pixel: `.` and `#` in hires; `.`, `1`, `2` and `3` in multicolor
+const fn art_pixel(pixel: u8, multicolor: bool) -> u8 {
+    match (pixel, multicolor) {
+        (b'.', _) => 0,
+        (b'#', false) => 1,
+        (b'1', true) => 1,
+        (b'2', true) => 2,
+        (b'3', true) => 3,
+        (_, false) => panic!("hires pixels must be '.' or '#'"),
+        (_, true) => panic!("multicolor pixels must be '.', '1', '2' or '3'"),
+    }
+}
+
+/// Pack ASCII art rows that are `hires_width` pixels wide in hires or half that in multicolor
+const fn pack_art<const N: usize>(rows: &[&str], height: usize, hires_width: usize) -> [u8; N] {
+    assert!(rows.len() == height, "wrong number of rows");
+    let width = rows[0].len();
+    let bits = match width {
+        w if w == hires_width => 1,
+        w if w == hires_width / 2 => 2,
+        _ => panic!("rows must be hires or multicolor width"),
+    };
+    let bytes_per_row = hires_width / 8;
+    let mut data = [0u8; N];
+    let mut y = 0;
+    while y < height {
+        let row = rows[y].as_bytes();
+        assert!(row.len() == width, "all rows must have the same width");
+        let mut x = 0;
+        while x < width {
+            let bit = x * bits;
+            let shift = 8 - bits - bit % 8;
+            data[y * bytes_per_row + bit / 8] |= art_pixel(row[x], bits == 2) << shift;
+            x += 1;
+        }
+        y += 1;
+    }
+    data
+}
+
+/// Pack 21 rows of ASCII art into 63 or 64 bytes of sprite data, see `sprite!`
+///
+/// Rows are 24 pixels wide for hires sprites and 12 for multicolor sprites.
+pub const fn pack_sprite<const N: usize>(rows: &[&str]) -> [u8; N] {
+    assert!(N == 63 || N == 64, "sprite data must be 63 or 64 bytes");
+    pack_art(rows, 21, 24)
+}
+
+/// Pack 8 rows of ASCII art into a glyph, see `glyph!`
+///
+/// Rows are 8 pixels wide for hires and 4 for multicolor characters.
+pub const fn pack_glyph(rows: &[&str]) -> [u8; 8] {
+    pack_art(rows, 8, 8)
+}
+
+/// Sprite data from ASCII art at _compile time_
+///
+/// Takes 21 rows of 24 hires pixels (`.` and `#`) or 12 multicolor pixels
+/// (`.`, `1`, `2` and `3`) and gives 63 bytes or a 64 byte block, depending
+/// on the type. Invalid art is a compile time error.
+///
+/// # Examples
+/// ~~~
+/// use mos_hardware::sprite;
+/// const BALL: [u8; 64] = sprite!(
+///     "....1111....",
+///     "..11111111..",
+///     ".1122222211.",
+///     ".1223333221.",
+///     "112233332211",
+///     "112233332211",
+///     "112233332211",
+///     ".1223333221.",
+///     ".1122222211.",
+///     "..11111111..",
+///     "....1111....",
+///     "............",
+///     "....3333....",
+///     "...333333...",
+///     "..33333333..",
+///     "..33333333..",
+///     "...333333...",
+///     "....3333....",
+///     "............",
+///     "............",
+///     "............",
+/// );
+/// ~~~
+#[macro_export]
+macro_rules! sprite {
+    ($($row:expr),+ $(,)?) => {
+        const { $crate::vic2::pack_sprite(&[$($row),+]) }
+    };
+}
+
+/// Character glyph from ASCII art at _compile time_
+///
+/// Takes 8 rows of 8 hires pixels (`.` and `#`) or 4 multicolor pixels
+/// (`.`, `1`, `2` and `3`). Invalid art is a compile time error.
+///
+/// # Examples
+/// ~~~
+/// use mos_hardware::glyph;
+/// const SMILEY: [u8; 8] = glyph!(
+///     "..####..",
+///     ".#....#.",
+///     "#.#..#.#",
+///     "#......#",
+///     "#.#..#.#",
+///     "#..##..#",
+///     ".#....#.",
+///     "..####..",
+/// );
+/// assert_eq!(SMILEY, [0x3c, 0x42, 0xa5, 0x81, 0xa5, 0x99, 0x42, 0x3c]);
+/// ~~~
+#[macro_export]
+macro_rules! glyph {
+    ($($row:expr),+ $(,)?) => {
+        const { $crate::vic2::pack_glyph(&[$($row),+]) }
+    };
+}
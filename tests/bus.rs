@@ -0,0 +1,171 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Register traces of chip support code on the host bus
+//!
+//! See the README for how to run these on the host.
+
+#![cfg(not(target_arch = "mos"))]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use mos_hardware::bus::{Access, Bus, HostBus};
+use mos_hardware::c64::Cpu6510;
+use mos_hardware::cia::{CIA2PortA, MOSComplexInterfaceAdapter6526_2, TimeOfDay};
+use mos_hardware::sid::SidTune;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn set_vic_bank() {
+    let bus = HostBus::new();
+    bus.poke(0xdd00, CIA2PortA::default().bits());
+    let cia2 = unsafe { bus.register::<MOSComplexInterfaceAdapter6526_2>(0xdd00) };
+    cia2.set_vic_bank(CIA2PortA::VIC_BANK_1);
+    assert_eq!(
+        bus.trace(),
+        [
+            Access::Read(0xdd00, 0b0011_1111),
+            Access::Read(0xdd02, 0b0000_0000),
+            Access::Write(0xdd02, 0b0000_0011),
+            Access::Write(0xdd00, 0b0011_1110),
+        ]
+    );
+}
+
+#[test]
+fn cpu_reset() {
+    let bus = HostBus::new();
+    unsafe { bus.register::<Cpu6510>(0x0000) }.reset();
+    assert_eq!(bus.writes(), [(0x0001, 0xf7), (0x0000, 0x2f)]);
+}
+
+#[test]
+fn set_time_of_day() {
+    let mut bus = HostBus::new();
+    let tod = unsafe { bus.register_mut::<TimeOfDay>(0xdc08) };
+    assert!(tod.set_time_bcd_bool(0x11, 0x59, 0x30, 0x05, true));
+    // hours first as that stops the clock until tenths are written
+    assert_eq!(
+        bus.writes(),
+        [
+            (0xdc0b, 0x91),
+            (0xdc0a, 0x59),
+            (0xdc09, 0x30),
+            (0xdc08, 0x05)
+        ]
+    );
+}
+
+#[test]
+fn set_invalid_time_of_day() {
+    let mut bus = HostBus::new();
+    let tod = unsafe { bus.register_mut::<TimeOfDay>(0xdc08) };
+    assert!(!tod.set_time_bcd_bool(0x13, 0x5a, 0x00, 0x00, false));
+    assert!(bus.writes().is_empty());
+}
+
+/// PSID v2 header with load address in the data, followed by three bytes of code
+struct Tune;
+
+impl SidTune for Tune {
+    const BYTES: &'static [u8] = &{
+        let mut bytes = [0u8; 0x7c + 5];
+        let header: [u8; 0x10] = *b"PSID\x00\x02\x00\x7c\x00\x00\x10\x00\x10\x03\x00\x03";
+        let mut i = 0;
+        while i < header.len() {
+            bytes[i] = header[i];
+            i += 1;
+        }
+        // load address 0x1000 followed by `rts; rts; rts`
+        bytes[0x7c] = 0x00;
+        bytes[0x7d] = 0x10;
+        bytes[0x7e] = 0x60;
+        bytes[0x7f] = 0x60;
+        bytes[0x80] = 0x60;
+        bytes
+    };
+}
+
+#[test]
+fn sid_tune_init_wrapper() {
+    assert_eq!(Tune.num_songs(), 3);
+    assert_eq!(Tune::LOAD_ADDRESS, 0x1000);
+    assert_eq!(Tune::DATA_LEN, 3);
+    let bus = HostBus::new();
+    let address = unsafe { Tune.write_init_wrapper(&bus, 2) };
+    assert_eq!(address, 0x1003);
+    // lda #2; jmp $1000
+    assert_eq!(
+        bus.writes(),
+        [
+            (0x1003, 0xa9),
+            (0x1004, 0x02),
+            (0x1005, 0x4c),
+            (0x1006, 0x00),
+            (0x1007, 0x10),
+        ]
+    );
+}
+
+#[test]
+fn read_hook() {
+    let mut bus = HostBus::new();
+    let mut line = 0u8;
+    bus.on_read(move |address, value| match address {
+        0xd012 => {
+            line = line.wrapping_add(1);
+            line
+        }
+        _ => value,
+    });
+    assert_eq!(bus.read(0xd012), 1);
+    assert_eq!(bus.read(0xd012), 2);
+    assert_eq!(bus.read(0xd011), 0);
+}
+
+#[test]
+fn hooks_can_access_registers() {
+    let mut bus = HostBus::new();
+    let cpu: &'static Cpu6510 = unsafe { &*(bus.register::<Cpu6510>(0x0000) as *const _) };
+    let writes = Rc::new(Cell::new(0));
+    let counter = writes.clone();
+    bus.on_read(move |_, value| {
+        cpu.reset();
+        value
+    });
+    bus.on_write(move |_, _| {
+        counter.set(counter.get() + 1);
+        cpu.reset();
+    });
+    bus.read(0x0002);
+    // the hooks are not called again from within themselves
+    assert_eq!(writes.get(), 2);
+    assert_eq!(bus.peek(0x0001), 0xf7);
+}
+
+#[test]
+#[should_panic(expected = "already active")]
+fn second_bus_panics() {
+    let _first = HostBus::new();
+    let _second = HostBus::new();
+}
+
+#[test]
+fn bus_can_be_recreated() {
+    drop(HostBus::new());
+    let bus = HostBus::new();
+    unsafe { bus.write(0xd020, 1) };
+    assert_eq!(bus.writes(), [(0xd020, 1)]);
+}
@@ -0,0 +1,144 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! CIA timer and joystick drivers running against the host bus
+//!
+//! See the README for how to run these on the host.
+
+#![cfg(not(target_arch = "mos"))]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use mos_hardware::bus::{Access, Bus, HostBus};
+use mos_hardware::cia::{
+    GameController, InterruptControl, MOSComplexInterfaceAdapter6526_1, Mos6526ControlBlock, Timer,
+    TimerControl, TimerMode,
+};
+use mos_hardware::joystick::{self, Joystick, Port};
+
+/// CIA1 interval timers, TOD clock and interrupt control
+const CIA1_CONTROL: u16 = 0xdc04;
+
+#[test]
+fn start_one_shot_timer() {
+    let bus = HostBus::new();
+    // TOD alarm bit must survive, the stale one-shot bit must not
+    bus.poke(0xdc0f, (TimerControl::ALARM | TimerControl::RUNMODE).bits());
+    let cia = unsafe { bus.register::<Mos6526ControlBlock>(CIA1_CONTROL) };
+    cia.start_timer(Timer::B, 0x03e8, TimerMode::OneShot);
+    let started =
+        TimerControl::ALARM | TimerControl::RUNMODE | TimerControl::LOAD | TimerControl::START;
+    assert_eq!(
+        bus.writes(),
+        [
+            (0xdc0f, TimerControl::ALARM.bits()),
+            (0xdc06, 0xe8),
+            (0xdc07, 0x03),
+            (0xdc0f, started.bits()),
+        ]
+    );
+    assert!(cia.is_timer_running(Timer::B));
+}
+
+#[test]
+fn stop_timer_keeps_counter() {
+    let bus = HostBus::new();
+    bus.load(0xdc04, &[0x34, 0x12]);
+    bus.poke(0xdc0e, TimerControl::START.bits());
+    let cia = unsafe { bus.register::<Mos6526ControlBlock>(CIA1_CONTROL) };
+    cia.stop_timer(Timer::A);
+    assert_eq!(bus.writes(), [(0xdc0e, 0)]);
+    assert!(!cia.is_timer_running(Timer::A));
+    assert_eq!(cia.timer_value(Timer::A), 0x1234);
+}
+
+#[test]
+fn timer_value_rereads_after_borrow() {
+    let mut bus = HostBus::new();
+    // successive 16-bit reads: the first catches the low byte before and the
+    // high byte after a borrow
+    let mut counter = [0x1100u16, 0x11ff, 0x11fe].into_iter();
+    let mut value = 0u16;
+    bus.on_read(move |address, stored| match address {
+        0xdc04 => {
+            value = counter.next().unwrap();
+            value as u8
+        }
+        0xdc05 => (value >> 8) as u8,
+        _ => stored,
+    });
+    let cia = unsafe { bus.register::<Mos6526ControlBlock>(CIA1_CONTROL) };
+    assert_eq!(cia.timer_value(Timer::A), 0x11fe);
+    assert_eq!(
+        bus.trace()
+            .iter()
+            .filter(|access| matches!(access, Access::Read(0xdc04, _)))
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn enable_and_disable_timer_interrupt() {
+    let bus = HostBus::new();
+    let cia = unsafe { bus.register::<Mos6526ControlBlock>(CIA1_CONTROL) };
+    cia.set_timer_interrupt(Timer::A, true);
+    cia.set_timer_interrupt(Timer::B, false);
+    assert_eq!(
+        bus.writes(),
+        [
+            (
+                0xdc0d,
+                (InterruptControl::SET_CLEAR | InterruptControl::TIMER_A).bits()
+            ),
+            (0xdc0d, InterruptControl::TIMER_B.bits()),
+        ]
+    );
+}
+
+/// Bus with joystick lines of control port 1 held low by `pressed`
+fn bus_with_port_one(pressed: GameController) -> HostBus {
+    let mut bus = HostBus::new();
+    bus.poke(0xdc00, 0x7f);
+    bus.on_read(move |address, stored| match address {
+        0xdc01 => !pressed.bits(),
+        _ => stored,
+    });
+    bus
+}
+
+#[test]
+fn read_joystick_restores_keyboard_column() {
+    let bus = bus_with_port_one(GameController::LEFT | GameController::FIRE);
+    let cia = unsafe { bus.register::<MOSComplexInterfaceAdapter6526_1>(0xdc00) };
+    let state = joystick::read(cia, Port::One);
+    assert_eq!(state, GameController::LEFT | GameController::FIRE);
+    // keyboard columns are deselected while reading and then restored
+    assert_eq!(bus.writes(), [(0xdc00, 0xff), (0xdc00, 0x7f)]);
+}
+
+#[test]
+fn joystick_fire_edges() {
+    let bus = bus_with_port_one(GameController::FIRE);
+    let cia = unsafe { bus.register::<MOSComplexInterfaceAdapter6526_1>(0xdc00) };
+    let mut stick = Joystick::new(Port::One);
+    stick.update(cia);
+    assert!(stick.fire_pressed());
+    stick.update(cia);
+    assert!(stick.is_fire_down() && !stick.fire_pressed());
+    assert_eq!(stick.direction(), (0, 0));
+    stick.update_with(GameController::UP);
+    assert!(stick.fire_released());
+    assert_eq!(stick.direction(), (0, -1));
+}
@@ -0,0 +1,78 @@
+// copyright 2026 mikael lund aka wombat
+//
+// licensed under the apache license, version 2.0 (the "license");
+// you may not use this file except in compliance with the license.
+// you may obtain a copy of the license at
+//
+//     http://www.apache.org/licenses/license-2.0
+//
+// unless required by applicable law or agreed to in writing, software
+// distributed under the license is distributed on an "as is" basis,
+// without warranties or conditions of any kind, either express or implied.
+// see the license for the specific language governing permissions and
+// limitations under the license.
+
+//! Checks of `VicLayout`, which are compile time errors in a `const` layout
+//!
+//! Doc tests are disabled for the crate, so the rejected layouts are exercised
+//! here at runtime. See the README for how to run these on the host.
+
+#![cfg(not(target_arch = "mos"))]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use mos_hardware::c64::layout::VicLayout;
+use mos_hardware::vic2::{CharsetBank, MemorySetup, ScreenBank};
+
+const LAYOUT: VicLayout = VicLayout::new(0x4400)
+    .charset(0x4800)
+    .sprites(0x5000, 8)
+    .validate();
+
+#[test]
+fn valid_layout() {
+    assert_eq!(LAYOUT.bank(), 1);
+    assert_eq!(LAYOUT.sprite_pointer(0x5040), 0x41);
+    assert_eq!(LAYOUT.sprite_pointers_address(), 0x47f8);
+    assert_eq!(
+        LAYOUT.memory_setup(),
+        MemorySetup::from_bits(0)
+            .screen(ScreenBank::AT_0400)
+            .charset(CharsetBank::AT_0800)
+    );
+}
+
+#[test]
+fn rom_charset_by_default_in_bank_0() {
+    let layout = VicLayout::new(0x0400).validate();
+    assert_eq!(
+        layout.memory_setup(),
+        MemorySetup::from_bits(0)
+            .screen(ScreenBank::AT_0400)
+            .charset(CharsetBank::DEFAULT)
+    );
+}
+
+#[test]
+#[should_panic(expected = "VIC banks 1 and 3 need a charset or bitmap")]
+fn no_charset_in_bank_1() {
+    VicLayout::new(0x4400).validate();
+}
+
+#[test]
+#[should_panic(expected = "VIC banks 1 and 3 need a charset or bitmap")]
+fn no_charset_in_bank_3_when_applied() {
+    VicLayout::new(0xc000).memory_setup();
+}
+
+#[test]
+#[should_panic(expected = "character ROM shadow")]
+fn charset_in_rom_shadow() {
+    VicLayout::new(0x0400).charset(0x1000);
+}
+
+#[test]
+#[should_panic(expected = "region extends past the end of memory")]
+fn sprites_past_end_of_memory() {
+    VicLayout::new(0xc000).charset(0xc800).sprites(0xffc0, 2);
+}